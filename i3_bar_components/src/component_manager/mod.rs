@@ -5,17 +5,11 @@ pub use component_manager_messenger::ComponentManagerMassenger;
 use log::*;
 use std::any::Any;
 use std::collections::HashMap;
-use std::sync::mpsc::Receiver;
-use std::{
-    io::{BufRead, Read, Stdout, Write},
-    time::SystemTime,
-};
-
-use crate::property::{self, Instance, Properties};
-use crate::{
-    components::prelude::*,
-    protocol::{ClickEvent, Header},
-};
+use std::time::SystemTime;
+
+use crate::output::{I3barOutput, Output};
+use crate::property::{Instance, Properties};
+use crate::{components::prelude::*, protocol::ClickEvent};
 
 use component_manager_messenger::{ComponentManagerMassengerQueue, Message};
 
@@ -33,8 +27,7 @@ impl<T: Component + Any> AnyComponent for T {}
 
 pub struct ComponentManager {
     layers: Vec<Vec<Box<dyn AnyComponent>>>,
-    event_reader: Receiver<ClickEvent>,
-    out_writer: Stdout,
+    output: Box<dyn Output>,
     last_update: SystemTime,
     global_event_listener: fn(&mut dyn ManageComponents, &ClickEvent),
     component_manager_messenger: ComponentManagerMassenger,
@@ -54,18 +47,20 @@ impl ComponentManager {
         self.handle_events();
         self.handle_messenges();
         self.update_components(dt);
-        if let Err(err) = self.build_json() {
+        let blocks = self
+            .layers
+            .last()
+            .into_iter()
+            .flatten()
+            .flat_map(|c| c.all_properties())
+            .collect::<Vec<&Properties>>();
+        if let Err(err) = self.output.write_blocks(&blocks) {
             error!("Could not write bytes: {:#?}", err);
-            return;
-        }
-        if self.out_writer.flush().is_err() {
-            error!("Error while flushing buffer");
         }
     }
 
     fn handle_events(&mut self) {
-        let event_reader = &mut self.event_reader;
-        let events = event_reader.try_iter().collect::<Vec<ClickEvent>>();
+        let events = self.output.click_events();
         let cmm = &mut self.component_manager_messenger;
         let global_event_listener = &self.global_event_listener;
         events.iter().for_each(|event| {
@@ -95,40 +90,6 @@ impl ComponentManager {
         self.get_layer_mut().iter_mut().for_each(|c| c.update(dt));
     }
 
-    fn build_json(&mut self) -> std::io::Result<()> {
-        let mut write = self.out_writer.lock();
-        write.write_all(&[b'['])?;
-        self.get_layer()
-            .iter()
-            .flat_map(|c| c.all_properties())
-            .enumerate()
-            .try_for_each(|(index, block)| {
-                if index != 0 {
-                    write.write_all(&[b','])?;
-                }
-                if block.padding.left > 0 {
-                    serde_json::to_writer(
-                        &mut write,
-                        &create_padding(block.padding.left, block.instance),
-                    )
-                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-                    write.write_all(&[b','])?;
-                }
-                serde_json::to_writer(&mut write, block)
-                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-                if block.padding.right > 0 {
-                    write.write_all(&[b','])?;
-                    serde_json::to_writer(
-                        &mut write,
-                        &create_padding(block.padding.right, block.instance),
-                    )
-                    .map_err(|e| std::io::Error::new(std::io::ErrorKind::Other, e))?;
-                }
-                Ok::<_, std::io::Error>(())
-            })?;
-        write.write_all(&[b']', b',', 10])
-    }
-
     pub fn get_component_mut<'a, T: Component + 'static>(
         &'a mut self,
         name: &str,
@@ -253,48 +214,8 @@ impl ManageComponents for ComponentManager {
     }
 }
 
-fn read_events(
-    reader: &mut dyn BufRead,
-    event_number: &mut usize,
-    tx: &std::sync::mpsc::Sender<ClickEvent>,
-) {
-    let mut event = String::new();
-
-    if reader.read_line(&mut event).is_err() {
-        error!("Could not read event from stdin");
-        return;
-    }
-
-    trace!("Raw event data: {}", event);
-
-    let mut new_event_number = *event_number + 1;
-
-    let event = match event_number {
-        0 => {
-            std::mem::swap(event_number, &mut new_event_number);
-            return;
-        }
-        1 => &event,
-        _ => &event[1..],
-    };
-
-    std::mem::swap(event_number, &mut new_event_number);
-
-    let click_event = match serde_json::from_str::<ClickEvent>(event) {
-        Ok(ev) => ev,
-        Err(e) => {
-            error!("Invalid click event {} \n {event}", e.to_string());
-            return;
-        }
-    };
-    if tx.send(click_event).is_err() {
-        debug!("No event rx found");
-    }
-}
-
 pub struct ComponentManagerBuilder {
-    stdin: Option<Box<dyn Read>>,
-    stdout: Option<Box<dyn Write>>,
+    output: Option<Box<dyn Output>>,
     click_events: bool,
 }
 
@@ -307,8 +228,7 @@ impl Default for ComponentManagerBuilder {
 impl ComponentManagerBuilder {
     pub fn new() -> Self {
         Self {
-            stdin: None,
-            stdout: None,
+            output: None,
             click_events: false,
         }
     }
@@ -322,61 +242,30 @@ impl ComponentManagerBuilder {
         self
     }
 
-    pub fn set_stdin(&mut self, stdin: Box<dyn Read>) {
-        self.stdin = Some(stdin);
+    /// Replaces the default i3bar output
+    pub fn set_output(&mut self, output: Box<dyn Output>) {
+        self.output = Some(output);
     }
 
-    pub fn with_stdin(mut self, stdin: Box<dyn Read>) -> Self {
-        self.set_stdin(stdin);
-        self
-    }
-
-    pub fn set_stdout(&mut self, stdout: Box<dyn Write>) {
-        self.stdout = Some(stdout);
-    }
-
-    pub fn with_stdout(mut self, stdout: Box<dyn Write>) -> Self {
-        self.set_stdout(stdout);
+    pub fn with_output(mut self, output: Box<dyn Output>) -> Self {
+        self.set_output(output);
         self
     }
 
     pub fn build(self) -> ComponentManager {
-        let mut out_writer = std::io::stdout();
-
-        let header = Header::new().with_click_events(self.click_events);
-        let header_buffer = match serde_json::to_vec(&header) {
-            Ok(hb) => hb,
-            Err(_) => {
-                debug!("Could not convert header to json {:#?}", header);
-                panic!("Could not convert header to json {:#?}", header)
-            }
-        };
-        if out_writer.write_all(&header_buffer).is_err() {
-            debug!("Could not write header");
-            panic!("Could not write header")
-        }
-
-        if out_writer.write_all(&[10, b'[']).is_err() {
-            debug!("Could not write json start");
-            panic!("Could not write json start")
-        }
-
-        let (tx, rx) = std::sync::mpsc::channel();
-
-        std::thread::spawn(move || {
-            let stdin = std::io::stdin();
-            let mut last_event_id = 0;
-            let tx = tx;
-
-            loop {
-                read_events(&mut stdin.lock(), &mut last_event_id, &tx);
+        let output = self.output.unwrap_or_else(|| {
+            match I3barOutput::new(std::io::stdout(), self.click_events) {
+                Ok(output) => Box::new(output),
+                Err(e) => {
+                    debug!("Could not write header {:#?}", e);
+                    panic!("Could not write header {:#?}", e)
+                }
             }
         });
 
         ComponentManager {
             layers: vec![Vec::new()],
-            event_reader: rx,
-            out_writer,
+            output,
             last_update: SystemTime::now(),
             global_event_listener: default_listener,
             component_manager_messenger: Default::default(),
@@ -384,15 +273,4 @@ impl ComponentManagerBuilder {
     }
 }
 
-fn create_padding(width: usize, instance: property::Instance) -> Properties {
-    Properties {
-        text: property::Text {
-            full: format!("{:<width$}", ""),
-            short: None,
-        },
-        instance,
-        ..Default::default()
-    }
-}
-
 fn default_listener(_: &mut dyn ManageComponents, _: &ClickEvent) {}
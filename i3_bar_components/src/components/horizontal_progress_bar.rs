@@ -0,0 +1,106 @@
+use super::{prelude::*, BaseComponent};
+
+const PARTIAL_BLOCKS: [char; 7] = [
+    '\u{258F}', '\u{258E}', '\u{258D}', '\u{258C}', '\u{258B}', '\u{258A}', '\u{2589}',
+];
+const FULL_BLOCK: char = '\u{2588}';
+
+pub struct HorizontalProgressBar {
+    base_component: BaseComponent,
+    current: f64,
+    max: f64,
+    width: usize,
+}
+
+impl HorizontalProgressBar {
+    pub fn new(max: f64, width: usize) -> Self {
+        Self {
+            base_component: BaseComponent::new(),
+            current: 0.0,
+            max,
+            width,
+        }
+    }
+
+    pub fn set_current(&mut self, current: f64) {
+        self.current = current;
+    }
+
+    fn render(&self) -> String {
+        let progress = if self.max > 0.0 {
+            (self.current / self.max).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        // Every char is split into eight steps
+        let steps = (progress * (self.width * 8) as f64).round() as usize;
+        let full = steps / 8;
+        let partial = steps % 8;
+
+        let mut bar = String::with_capacity(self.width * 3);
+        (0..full).for_each(|_| bar.push(FULL_BLOCK));
+        if partial > 0 {
+            bar.push(PARTIAL_BLOCKS[partial - 1]);
+        }
+        let used = full + usize::from(partial > 0);
+        (used..self.width).for_each(|_| bar.push(' '));
+        bar
+    }
+}
+
+impl SimpleComponent for HorizontalProgressBar {
+    fn properties(&self) -> &crate::property::Properties {
+        self.base_component.properties()
+    }
+    fn properties_mut(&mut self) -> &mut crate::property::Properties {
+        self.base_component.properties_mut()
+    }
+}
+
+impl Component for HorizontalProgressBar {
+    fn update(&mut self, _: f64) {
+        self.set_full(self.render())
+    }
+
+    fn all_properties<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = &'a crate::property::Properties> + 'a> {
+        Box::new([self.properties()].into_iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_empty() {
+        let bar = HorizontalProgressBar::new(100.0, 4);
+        assert_eq!(bar.render(), "    ");
+    }
+
+    #[test]
+    fn render_full() {
+        let mut bar = HorizontalProgressBar::new(100.0, 4);
+        bar.set_current(100.0);
+        assert_eq!(bar.render(), "\u{2588}\u{2588}\u{2588}\u{2588}");
+        bar.set_current(250.0);
+        assert_eq!(bar.render(), "\u{2588}\u{2588}\u{2588}\u{2588}");
+    }
+
+    #[test]
+    fn render_partial() {
+        let mut bar = HorizontalProgressBar::new(100.0, 4);
+        bar.set_current(50.0);
+        assert_eq!(bar.render(), "\u{2588}\u{2588}  ");
+        bar.set_current(62.5);
+        assert_eq!(bar.render(), "\u{2588}\u{2588}\u{258C} ");
+    }
+
+    #[test]
+    fn render_zero_max() {
+        let mut bar = HorizontalProgressBar::new(0.0, 2);
+        bar.set_current(10.0);
+        assert_eq!(bar.render(), "  ");
+    }
+}
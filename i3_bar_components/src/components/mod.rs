@@ -1,11 +1,13 @@
 mod button;
 mod button_group;
+mod horizontal_progress_bar;
 mod label;
 pub mod prelude;
 mod progress_bar;
 
 pub use button::Button;
 pub use button_group::{ButtonGroup, GroupButton};
+pub use horizontal_progress_bar::HorizontalProgressBar;
 pub use label::Label;
 pub use progress_bar::ProgressBar;
 
@@ -1,5 +1,6 @@
 pub mod component_manager;
 pub mod components;
+pub mod output;
 pub mod property;
 pub mod protocol;
 pub mod string;
@@ -0,0 +1,375 @@
+use log::*;
+use std::{
+    io::{BufRead, Stdout, Write},
+    sync::mpsc::Receiver,
+};
+
+use crate::{
+    property::{self, Properties},
+    protocol::{ClickEvent, Header},
+};
+
+/// Destination of the rendered components
+pub trait Output {
+    /// Shows the blocks of the visible layer
+    fn write_blocks(&mut self, blocks: &[&Properties]) -> std::io::Result<()>;
+
+    /// Click events received since the last call
+    fn click_events(&mut self) -> Vec<ClickEvent> {
+        Vec::new()
+    }
+}
+
+/// i3bar protocol. Blocks are written as an endless JSON array to stdout and
+/// click events are read from stdin.
+pub struct I3barOutput<W: Write = Stdout> {
+    out_writer: W,
+    event_reader: Option<Receiver<ClickEvent>>,
+}
+
+impl<W: Write> I3barOutput<W> {
+    /// Writes the protocol header. Click events are only read if `click_events` is set.
+    pub fn new(mut out_writer: W, click_events: bool) -> std::io::Result<Self> {
+        let header = Header::new().with_click_events(click_events);
+        serde_json::to_writer(&mut out_writer, &header).map_err(std::io::Error::other)?;
+        out_writer.write_all(b"\n[")?;
+
+        let event_reader = click_events.then(|| {
+            let (tx, rx) = std::sync::mpsc::channel();
+            std::thread::spawn(move || {
+                let stdin = std::io::stdin();
+                let mut last_event_id = 0;
+                loop {
+                    read_events(&mut stdin.lock(), &mut last_event_id, &tx);
+                }
+            });
+            rx
+        });
+
+        Ok(Self {
+            out_writer,
+            event_reader,
+        })
+    }
+}
+
+impl<W: Write> Output for I3barOutput<W> {
+    fn write_blocks(&mut self, blocks: &[&Properties]) -> std::io::Result<()> {
+        let write = &mut self.out_writer;
+        write.write_all(b"[")?;
+        blocks.iter().enumerate().try_for_each(|(index, block)| {
+            if index != 0 {
+                write.write_all(b",")?;
+            }
+            if block.padding.left > 0 {
+                serde_json::to_writer(
+                    &mut *write,
+                    &create_padding(block.padding.left, block.instance),
+                )
+                .map_err(std::io::Error::other)?;
+                write.write_all(b",")?;
+            }
+            serde_json::to_writer(&mut *write, block).map_err(std::io::Error::other)?;
+            if block.padding.right > 0 {
+                write.write_all(b",")?;
+                serde_json::to_writer(
+                    &mut *write,
+                    &create_padding(block.padding.right, block.instance),
+                )
+                .map_err(std::io::Error::other)?;
+            }
+            Ok::<_, std::io::Error>(())
+        })?;
+        write.write_all(b"],\n")?;
+        write.flush()
+    }
+
+    fn click_events(&mut self) -> Vec<ClickEvent> {
+        self.event_reader
+            .as_ref()
+            .map(|rx| rx.try_iter().collect())
+            .unwrap_or_default()
+    }
+}
+
+/// One JSON object per line like `{"text":"..."}` for custom modules of bars like waybar or polybar.
+/// The text of all blocks is joined and uses pango markup. `class` is set to `urgent` if any block is urgent.
+/// Clicks are not supported.
+pub struct JsonLinesOutput<W: Write = Stdout> {
+    out_writer: W,
+}
+
+impl<W: Write> JsonLinesOutput<W> {
+    pub fn new(out_writer: W) -> Self {
+        Self { out_writer }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct JsonLine {
+    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    class: Option<&'static str>,
+}
+
+impl<W: Write> Output for JsonLinesOutput<W> {
+    fn write_blocks(&mut self, blocks: &[&Properties]) -> std::io::Result<()> {
+        let text = blocks
+            .iter()
+            .map(|block| {
+                let text = match block.markup {
+                    property::Markup::Pango => block.text.full.clone(),
+                    property::Markup::None => escape(&block.text.full),
+                };
+                format!(
+                    "{:left$}{text}{:right$}",
+                    "",
+                    "",
+                    left = block.padding.left,
+                    right = block.padding.right
+                )
+            })
+            .collect();
+        let line = JsonLine {
+            text,
+            class: blocks.iter().any(|b| b.urgent).then_some("urgent"),
+        };
+        serde_json::to_writer(&mut self.out_writer, &line).map_err(std::io::Error::other)?;
+        self.out_writer.write_all(b"\n")?;
+        self.out_writer.flush()
+    }
+}
+
+/// Single line per update with polybar formatting tags, also understood by lemonbar.
+/// Left, middle and right clicks run `click_command` followed by the instance and the button.
+/// The command is expected to pass the click back through `event_reader`.
+pub struct PolybarOutput<W: Write = Stdout> {
+    out_writer: W,
+    click_command: String,
+    event_reader: Receiver<ClickEvent>,
+}
+
+impl<W: Write> PolybarOutput<W> {
+    pub fn new(out_writer: W, click_command: &str, event_reader: Receiver<ClickEvent>) -> Self {
+        Self {
+            out_writer,
+            // Colons end the command of an action tag
+            click_command: click_command.replace(':', "\\:"),
+            event_reader,
+        }
+    }
+}
+
+impl<W: Write> Output for PolybarOutput<W> {
+    fn write_blocks(&mut self, blocks: &[&Properties]) -> std::io::Result<()> {
+        let mut line = String::new();
+        for block in blocks {
+            let instance = usize::from(block.instance);
+            for button in 1..=3 {
+                line.push_str(&format!(
+                    "%{{A{button}:{} {instance} {button}:}}",
+                    self.click_command
+                ));
+            }
+            let color = &block.color;
+            if let Some(text) = &color.text {
+                line.push_str(&format!("%{{F{text}}}"));
+            }
+            if let Some(background) = &color.background {
+                line.push_str(&format!("%{{B{background}}}"));
+            }
+            if block.urgent {
+                line.push_str("%{R}");
+            }
+            let text = match block.markup {
+                property::Markup::Pango => strip_pango(&block.text.full),
+                property::Markup::None => block.text.full.clone(),
+            };
+            line.push_str(&format!(
+                "{:left$}{}{:right$}",
+                "",
+                text.replace('%', "%%").replace('\n', " "),
+                "",
+                left = block.padding.left,
+                right = block.padding.right
+            ));
+            if block.urgent {
+                line.push_str("%{R}");
+            }
+            if color.background.is_some() {
+                line.push_str("%{B-}");
+            }
+            if color.text.is_some() {
+                line.push_str("%{F-}");
+            }
+            line.push_str("%{A}%{A}%{A}");
+        }
+        line.push('\n');
+        self.out_writer.write_all(line.as_bytes())?;
+        self.out_writer.flush()
+    }
+
+    fn click_events(&mut self) -> Vec<ClickEvent> {
+        self.event_reader.try_iter().collect()
+    }
+}
+
+/// Removes tags and decodes the entities escaped by pango
+fn strip_pango(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut in_tag = false;
+    for c in text.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn read_events(
+    reader: &mut dyn BufRead,
+    event_number: &mut usize,
+    tx: &std::sync::mpsc::Sender<ClickEvent>,
+) {
+    let mut event = String::new();
+
+    if reader.read_line(&mut event).is_err() {
+        error!("Could not read event from stdin");
+        return;
+    }
+
+    trace!("Raw event data: {}", event);
+
+    let mut new_event_number = *event_number + 1;
+
+    let event = match event_number {
+        0 => {
+            std::mem::swap(event_number, &mut new_event_number);
+            return;
+        }
+        1 => &event,
+        _ => &event[1..],
+    };
+
+    std::mem::swap(event_number, &mut new_event_number);
+
+    let click_event = match serde_json::from_str::<ClickEvent>(event) {
+        Ok(ev) => ev,
+        Err(e) => {
+            error!("Invalid click event {} \n {event}", e.to_string());
+            return;
+        }
+    };
+    if tx.send(click_event).is_err() {
+        debug!("No event rx found");
+    }
+}
+
+fn create_padding(width: usize, instance: property::Instance) -> Properties {
+    Properties {
+        text: property::Text {
+            full: format!("{:<width$}", ""),
+            short: None,
+        },
+        instance,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{I3barOutput, JsonLinesOutput, Output, PolybarOutput};
+    use crate::property::{Markup, Padding, Properties};
+    use crate::protocol::ClickEvent;
+
+    fn block(text: &str) -> Properties {
+        Properties {
+            text: text.to_owned().into(),
+            instance: 7.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn i3bar_output() {
+        let mut output = I3barOutput::new(Vec::new(), false).unwrap();
+        let mut padded = block("a");
+        padded.padding = Padding { left: 1, right: 0 };
+        output.write_blocks(&[&padded, &block("b")]).unwrap();
+        let written = String::from_utf8(output.out_writer).unwrap();
+        let (header, body) = written.split_once('\n').unwrap();
+        assert_eq!(header, r#"{"version":1,"click_events":false}"#);
+        let blocks = serde_json::from_str::<serde_json::Value>(
+            body.strip_prefix('[').unwrap().strip_suffix(",\n").unwrap(),
+        )
+        .unwrap();
+        let texts = blocks
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|b| b["full_text"].as_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(texts, [" ", "a", "b"]);
+    }
+
+    #[test]
+    fn json_lines_output() {
+        let mut output = JsonLinesOutput::new(Vec::new());
+        let mut pango = block("<b>b</b>");
+        pango.markup = Markup::Pango;
+        pango.padding = Padding { left: 0, right: 1 };
+        output.write_blocks(&[&block("a & "), &pango]).unwrap();
+        let mut urgent = block("c");
+        urgent.urgent = true;
+        output.write_blocks(&[&urgent]).unwrap();
+        assert_eq!(
+            String::from_utf8(output.out_writer).unwrap(),
+            "{\"text\":\"a &amp; <b>b</b> \"}\n{\"text\":\"c\",\"class\":\"urgent\"}\n"
+        );
+    }
+
+    #[test]
+    fn polybar_output() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut output = PolybarOutput::new(Vec::new(), "bar ctl click", rx);
+        let mut colored = block("50%");
+        colored.color.text = Some("#FFFFFF".into());
+        colored.color.background = Some("#FF0000".into());
+        let mut pango = block("<b>a &amp; b</b>");
+        pango.markup = Markup::Pango;
+        pango.urgent = true;
+        pango.padding = Padding { left: 1, right: 1 };
+        output.write_blocks(&[&colored, &pango]).unwrap();
+        assert_eq!(
+            String::from_utf8(std::mem::take(&mut output.out_writer)).unwrap(),
+            "%{A1:bar ctl click 7 1:}%{A2:bar ctl click 7 2:}%{A3:bar ctl click 7 3:}%{F#FFFFFF}%{B#FF0000}50%%%{B-}%{F-}%{A}%{A}%{A}\
+             %{A1:bar ctl click 7 1:}%{A2:bar ctl click 7 2:}%{A3:bar ctl click 7 3:}%{R} a & b %{R}%{A}%{A}%{A}\n"
+        );
+
+        tx.send(ClickEvent::new(7, 3)).unwrap();
+        let events = output.click_events();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].get_instance(), Some(7));
+        assert_eq!(events[0].get_button(), 3);
+    }
+
+    #[test]
+    fn polybar_escapes_colons_in_command() {
+        let (_, rx) = std::sync::mpsc::channel();
+        let output = PolybarOutput::new(Vec::<u8>::new(), "a:b", rx);
+        assert_eq!(output.click_command, "a\\:b");
+    }
+}
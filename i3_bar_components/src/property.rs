@@ -134,6 +134,12 @@ impl Default for Instance {
     }
 }
 
+impl From<Instance> for usize {
+    fn from(instance: Instance) -> Self {
+        instance.0
+    }
+}
+
 impl PartialEq<usize> for Instance {
     fn eq(&self, other: &usize) -> bool {
         self.0 == *other
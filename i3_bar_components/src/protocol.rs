@@ -66,6 +66,24 @@ pub struct ClickEvent {
 }
 
 impl ClickEvent {
+    /// Click on `instance` without position. Used by bars which only report the clicked block and button.
+    pub fn new(instance: usize, button: usize) -> Self {
+        Self {
+            name: None,
+            instance: Some(instance),
+            button,
+            modifiers: None,
+            x: 0,
+            y: 0,
+            relative_x: 0,
+            relative_y: 0,
+            output_x: 0,
+            output_y: 0,
+            width: 0,
+            height: 0,
+        }
+    }
+
     pub fn get_name(&self) -> &Option<String> {
         &self.name
     }
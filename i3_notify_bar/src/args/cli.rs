@@ -1,6 +1,8 @@
 use log::LevelFilter;
 use notify_server::notification::Urgency;
 
+use crate::{markup::MarkupMode, notification_bar::MinimalUrgency, output::OutputMode};
+
 use emoji::EmojiMode;
 
 #[derive(clap::Parser)]
@@ -28,6 +30,10 @@ pub struct Args {
     #[clap(long)]
     pub emoji_file: Option<String>,
 
+    /// override default icon file
+    #[clap(long)]
+    pub icon_file: Option<String>,
+
     ///Time between refresh in ms
     #[clap(short, long, default_value = "250")]
     pub refresh_rate: u64,
@@ -39,6 +45,42 @@ pub struct Args {
     /// How fast the text is animated
     #[clap(short, long, default_value = "5")]
     pub animation_chars_per_second: usize,
+
+    /// Number of closed notifications kept in the history
+    #[clap(long, default_value = "20")]
+    pub history_size: usize,
+
+    /// override default history file
+    #[clap(long)]
+    pub history_file: Option<String>,
+
+    /// Do not store notifications across restarts
+    #[clap(long)]
+    pub no_persist_history: bool,
+
+    /// override default control socket location
+    #[clap(long)]
+    pub socket_file: Option<String>,
+
+    /// Append every received notification to this file. The file can be used with replay
+    #[clap(long)]
+    pub record: Option<String>,
+
+    /// Command receiving copied text on stdin
+    #[clap(long, default_value = crate::clipboard::DEFAULT_COMMAND)]
+    pub clipboard_command: String,
+
+    /// Command opening links from notifications. The link is appended as last argument
+    #[clap(long, default_value = crate::link::DEFAULT_OPENER)]
+    pub link_opener: String,
+
+    /// How markup in notification bodies is handled. Allowed values: "strip", "pango"
+    #[clap(long, default_value = "strip")]
+    pub markup: MarkupMode,
+
+    /// Output format. Allowed values: "i3bar", "json-lines", "waybar", "polybar"
+    #[clap(long, default_value = "i3bar")]
+    pub output: OutputMode,
     pub config_file: Option<String>,
     #[clap(subcommand)]
     pub command: Option<Command>,
@@ -48,6 +90,40 @@ pub struct Args {
 pub enum Command {
     DebugConfig(DebugConfig),
     Run,
+    /// Send a command to the running bar
+    Ctl(Ctl),
+    /// Run recorded notifications through the rules and print the results as JSON lines
+    Replay {
+        /// File created with --record. Use "-" to read from stdin
+        file: String,
+    },
+}
+
+#[derive(clap::Parser, Debug)]
+pub struct Ctl {
+    #[clap(subcommand)]
+    pub command: CtlCommand,
+}
+
+#[derive(clap::Parser, Debug)]
+pub enum CtlCommand {
+    /// Close a notification. Closes the newest notification if no id is given
+    Close {
+        #[clap(long)]
+        id: Option<u32>,
+    },
+    /// Close all notifications
+    CloseAll,
+    /// Invoke an action of a notification
+    InvokeAction { id: u32, action: String },
+    /// Allowed values: "low", "normal", "critical", "off"
+    SetMinUrgency { urgency: MinimalUrgency },
+    /// Toggle do not disturb
+    ToggleDnd,
+    /// Print all active notifications as JSON
+    List,
+    /// Report a click on a block. Used by the polybar output
+    Click { instance: usize, button: usize },
 }
 
 #[derive(clap::Parser, Debug)]
@@ -66,4 +142,19 @@ pub struct DebugConfig {
     pub urgency: Urgency,
     #[clap(long, default_value = "0")]
     pub expire_timeout: i32,
+    /// Value of the category hint
+    #[clap(long)]
+    pub category: Option<String>,
+    /// Value of the desktop-entry hint
+    #[clap(long)]
+    pub desktop_entry: Option<String>,
+    /// Set the transient hint
+    #[clap(long)]
+    pub transient: bool,
+    /// Set the resident hint
+    #[clap(long)]
+    pub resident: bool,
+    /// Value of the value hint (progress in percent)
+    #[clap(long)]
+    pub value: Option<i32>,
 }
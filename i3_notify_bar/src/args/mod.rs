@@ -3,7 +3,9 @@ use std::path::PathBuf;
 use clap::Parser;
 use emoji::EmojiMode;
 use log::LevelFilter;
-use notify_server::notification::Urgency;
+use notify_server::notification::{Hints, Urgency};
+
+use crate::{control::ControlCommand, markup::MarkupMode, output::OutputMode};
 
 mod cli;
 
@@ -16,18 +18,30 @@ pub struct Args {
     pub log_level: LevelFilter,
     pub log_file: Option<PathBuf>,
     pub emoji_file: Option<PathBuf>,
+    pub icon_file: Option<PathBuf>,
     pub refresh_rate: u64,
     pub max_text_length: usize,
     pub animation_chars_per_second: usize,
+    pub history_size: usize,
+    pub history_file: Option<PathBuf>,
+    pub persist_history: bool,
+    pub socket_file: Option<PathBuf>,
+    pub record_file: Option<PathBuf>,
+    pub clipboard_command: Vec<String>,
+    pub link_opener: Vec<String>,
+    pub markup_mode: MarkupMode,
+    pub output_mode: OutputMode,
     pub config_file: Option<PathBuf>,
     pub command: Command,
 }
 
 #[derive(Default)]
 pub enum Command {
-    DebugConfig(DebugConfig),
+    DebugConfig(Box<DebugConfig>),
     #[default]
     Run,
+    Ctl(ControlCommand),
+    Replay(PathBuf),
 }
 
 pub struct DebugConfig {
@@ -38,6 +52,7 @@ pub struct DebugConfig {
     pub body: String,
     pub urgency: Urgency,
     pub expire_timeout: i32,
+    pub hints: Hints,
 }
 
 impl From<cli::Args> for Args {
@@ -47,9 +62,27 @@ impl From<cli::Args> for Args {
             log_level: cli_args.log_level,
             log_file: cli_args.log_file.map(Into::into),
             emoji_file: cli_args.emoji_file.map(Into::into),
+            icon_file: cli_args.icon_file.map(Into::into),
             refresh_rate: cli_args.refresh_rate,
             max_text_length: cli_args.max_text_length,
             animation_chars_per_second: cli_args.animation_chars_per_second,
+            history_size: cli_args.history_size,
+            history_file: cli_args.history_file.map(Into::into),
+            persist_history: !cli_args.no_persist_history,
+            socket_file: cli_args.socket_file.map(Into::into),
+            record_file: cli_args.record.map(Into::into),
+            clipboard_command: cli_args
+                .clipboard_command
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect(),
+            link_opener: cli_args
+                .link_opener
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect(),
+            markup_mode: cli_args.markup,
+            output_mode: cli_args.output,
             config_file: cli_args.config_file.map(Into::into),
             command: cli_args.command.into(),
         }
@@ -59,8 +92,10 @@ impl From<cli::Args> for Args {
 impl From<Option<cli::Command>> for Command {
     fn from(c: Option<cli::Command>) -> Self {
         c.map(|c| match c {
-            cli::Command::DebugConfig(dc) => Command::DebugConfig(dc.into()),
+            cli::Command::DebugConfig(dc) => Command::DebugConfig(Box::new(dc.into())),
             cli::Command::Run => Command::Run,
+            cli::Command::Ctl(ctl) => Command::Ctl(ctl.command.into()),
+            cli::Command::Replay { file } => Command::Replay(file.into()),
         })
         .unwrap_or_default()
     }
@@ -76,6 +111,32 @@ impl From<cli::DebugConfig> for DebugConfig {
             body: dc.body,
             urgency: dc.urgency,
             expire_timeout: dc.expire_timeout,
+            hints: Hints {
+                category: dc.category,
+                desktop_entry: dc.desktop_entry,
+                transient: dc.transient,
+                resident: dc.resident,
+                value: dc.value,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl From<cli::CtlCommand> for ControlCommand {
+    fn from(c: cli::CtlCommand) -> Self {
+        match c {
+            cli::CtlCommand::Close { id } => ControlCommand::Close { id },
+            cli::CtlCommand::CloseAll => ControlCommand::CloseAll,
+            cli::CtlCommand::InvokeAction { id, action } => {
+                ControlCommand::InvokeAction { id, action }
+            }
+            cli::CtlCommand::SetMinUrgency { urgency } => ControlCommand::SetMinUrgency { urgency },
+            cli::CtlCommand::ToggleDnd => ControlCommand::ToggleDnd,
+            cli::CtlCommand::List => ControlCommand::List,
+            cli::CtlCommand::Click { instance, button } => {
+                ControlCommand::Click { instance, button }
+            }
         }
     }
 }
@@ -0,0 +1,52 @@
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use log::error;
+
+pub const DEFAULT_COMMAND: &str = "xclip -selection clipboard";
+
+static COMMAND: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Sets the command receiving the copied text on stdin. Only the first call has an effect.
+pub fn set_command(command: Vec<String>) {
+    let _ = COMMAND.set(command);
+}
+
+/// Puts `text` into the clipboard by piping it into the clipboard command
+pub fn copy(text: &str) {
+    let command = COMMAND.get_or_init(|| {
+        DEFAULT_COMMAND
+            .split_whitespace()
+            .map(str::to_owned)
+            .collect()
+    });
+    let Some((program, args)) = command.split_first() else {
+        return;
+    };
+    let child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    let mut child = match child {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Could not copy to clipboard error: {}", e);
+            return;
+        }
+    };
+    let text = text.to_owned();
+    // xclip keeps running until another application takes the selection
+    std::thread::spawn(move || {
+        if let Some(mut stdin) = child.stdin.take() {
+            if let Err(e) = stdin.write_all(text.as_bytes()) {
+                error!("Could not copy to clipboard error: {}", e);
+            }
+        }
+        let _ = child.wait();
+    });
+}
@@ -5,11 +5,12 @@ use i3_bar_components::components::{
 use notify_server::notification::Action;
 
 use crate::{
-    icons,
+    clipboard, icons, link,
     notification_bar::{InvokeAction as _, NotificationManagerCommands},
 };
 pub struct ActionBar {
     buttons: Vec<ActionButton>,
+    builtin_buttons: Vec<BuiltinButton>,
     close_btn: Button,
     notification_id: notify_server::NotificationId,
     notification_manager_cmd: NotificationManagerCommands,
@@ -37,11 +38,38 @@ impl ActionBar {
 
         Self {
             buttons,
+            builtin_buttons: Vec::new(),
             notification_id,
             close_btn,
             notification_manager_cmd,
         }
     }
+
+    /// Adds a button opening each link
+    pub fn with_links(mut self, links: &[String]) -> Self {
+        self.builtin_buttons
+            .extend(links.iter().enumerate().map(|(i, link)| BuiltinButton {
+                button: Button::new(Box::new(format!(" open link {} ", i + 1))),
+                action: BuiltinAction::OpenLink(link.clone()),
+            }));
+        self
+    }
+
+    /// Adds buttons copying text to the clipboard. Each entry is a label and the text to copy.
+    /// Entries with empty text are skipped.
+    pub fn with_copy(mut self, entries: impl IntoIterator<Item = (String, String)>) -> Self {
+        let icon = icons::get_icon("copy").map_or("copy".to_owned(), |i| i.to_string());
+        self.builtin_buttons.extend(
+            entries
+                .into_iter()
+                .filter(|(_, text)| !text.is_empty())
+                .map(|(label, text)| BuiltinButton {
+                    button: Button::new(Box::new(format!(" {icon} {label} "))),
+                    action: BuiltinAction::Copy(text),
+                }),
+        );
+        self
+    }
 }
 
 impl Component for ActionBar {
@@ -52,13 +80,17 @@ impl Component for ActionBar {
             self.buttons
                 .iter()
                 .flat_map(Component::all_properties)
+                .chain(self.builtin_buttons.iter().map(|b| b.button.properties()))
                 .chain(self.close_btn.all_properties()),
         )
     }
 
     fn update(&mut self, dt: f64) {
         self.close_btn.update(dt);
-        self.buttons.iter_mut().for_each(|b| b.update(dt))
+        self.buttons.iter_mut().for_each(|b| b.update(dt));
+        self.builtin_buttons
+            .iter_mut()
+            .for_each(|b| b.button.update(dt))
     }
 
     fn event_targets<'a>(
@@ -75,6 +107,11 @@ impl Component for ActionBar {
             self.buttons
                 .iter()
                 .map(|b| (b.instance(), self as *const _))
+                .chain(
+                    self.builtin_buttons
+                        .iter()
+                        .map(|b| (b.button.instance(), self as *const _)),
+                )
                 .chain(std::iter::once((
                     self.close_btn.instance(),
                     self as *const _,
@@ -106,9 +143,31 @@ impl EventTarget for ActionBar {
             self.notification_manager_cmd
                 .action_invoked(self.notification_id, &button.key)
         }
+
+        let builtin_button = self
+            .builtin_buttons
+            .iter()
+            .find(|b| b.button.instance() == event_element);
+
+        match builtin_button.map(|b| &b.action) {
+            Some(BuiltinAction::OpenLink(url)) => link::open(url),
+            Some(BuiltinAction::Copy(text)) => clipboard::copy(text),
+            None => {}
+        }
     }
 }
 
+/// Button provided by the bar instead of the sending application
+struct BuiltinButton {
+    button: Button,
+    action: BuiltinAction,
+}
+
+enum BuiltinAction {
+    OpenLink(String),
+    Copy(String),
+}
+
 struct ActionButton {
     button: Button,
     key: String,
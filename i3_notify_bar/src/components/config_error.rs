@@ -0,0 +1,162 @@
+use std::sync::{Arc, RwLock};
+
+use i3_bar_components::{
+    components::{prelude::*, Button, Label},
+    protocol::ClickEvent,
+    string::AnimatedString,
+    ManageComponents,
+};
+
+use crate::icons;
+
+/// Shows the number of config errors as an urgent block. Clicking it opens the list of errors.
+/// Hidden while there are no errors.
+pub struct ConfigError {
+    errors: Arc<RwLock<Vec<String>>>,
+    shown: Vec<String>,
+    label: Option<Label<String>>,
+    max_width: usize,
+    move_chars_per_sec: usize,
+}
+
+impl ConfigError {
+    pub fn new(
+        errors: Arc<RwLock<Vec<String>>>,
+        max_width: usize,
+        move_chars_per_sec: usize,
+    ) -> Self {
+        Self {
+            errors,
+            shown: Vec::new(),
+            label: None,
+            max_width,
+            move_chars_per_sec,
+        }
+    }
+}
+
+impl Component for ConfigError {
+    fn update(&mut self, dt: f64) {
+        let errors = self.errors.read().unwrap_or_else(|e| e.into_inner());
+        if *errors != self.shown {
+            self.shown = errors.clone();
+            self.label = match self.shown.len() {
+                0 => None,
+                count => {
+                    let icon =
+                        icons::get_icon("warning").map_or(String::new(), |i| format!("{i} "));
+                    let plural = if count == 1 { "" } else { "s" };
+                    let mut label = Label::new(format!(" {icon}{count} config error{plural} "));
+                    label.set_urgent(true);
+                    Some(label)
+                }
+            };
+        }
+        drop(errors);
+        if let Some(label) = self.label.as_mut() {
+            label.update(dt);
+        }
+    }
+
+    fn all_properties<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = &'a i3_bar_components::property::Properties> + 'a> {
+        Box::new(self.label.iter().flat_map(Component::all_properties))
+    }
+
+    fn event_targets<'a>(
+        &'a self,
+    ) -> Box<
+        dyn Iterator<
+                Item = (
+                    i3_bar_components::property::Instance,
+                    *const dyn EventTarget,
+                ),
+            > + 'a,
+    > {
+        Box::new(
+            self.label
+                .iter()
+                .map(|l| (l.instance(), self as *const dyn EventTarget)),
+        )
+    }
+}
+
+impl EventTarget for ConfigError {
+    fn event(&mut self, mc: &mut dyn ManageComponents, event: &ClickEvent) {
+        if event.get_button() != 1 {
+            return;
+        }
+        mc.new_layer();
+        mc.add_component(Box::new(ConfigErrorList::new(
+            &self.shown,
+            self.max_width,
+            self.move_chars_per_sec,
+        )));
+    }
+}
+
+/// Layer listing all config errors
+struct ConfigErrorList {
+    labels: Vec<Label<AnimatedString>>,
+    close_btn: Button,
+}
+
+impl ConfigErrorList {
+    fn new(errors: &[String], max_width: usize, move_chars_per_sec: usize) -> Self {
+        let labels = errors
+            .iter()
+            .map(|e| {
+                let mut label = Label::new(
+                    AnimatedString::new(format!(" {} ", e.replace('\n', " ")))
+                        .with_max_width(max_width)
+                        .with_move_chars_per_sec(move_chars_per_sec),
+                );
+                label.set_urgent(true);
+                label
+            })
+            .collect();
+        let btn_text = format!(
+            " {} ",
+            icons::get_icon("close").map_or("close".to_owned(), |i| i.to_string())
+        );
+        let mut close_btn = Button::new(Box::new(btn_text));
+        close_btn.set_on_click(|_, mc, ce| {
+            if ce.get_button() == 1 {
+                mc.pop_layer();
+            }
+        });
+        Self { labels, close_btn }
+    }
+}
+
+impl Component for ConfigErrorList {
+    fn update(&mut self, dt: f64) {
+        self.labels.iter_mut().for_each(|l| l.update(dt));
+        self.close_btn.update(dt);
+    }
+
+    fn all_properties<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = &'a i3_bar_components::property::Properties> + 'a> {
+        Box::new(
+            self.labels
+                .iter()
+                .flat_map(Component::all_properties)
+                .chain(self.close_btn.all_properties()),
+        )
+    }
+
+    fn event_targets<'a>(
+        &'a self,
+    ) -> Box<
+        dyn Iterator<
+                Item = (
+                    i3_bar_components::property::Instance,
+                    *const dyn EventTarget,
+                ),
+            > + 'a,
+    > {
+        self.close_btn.event_targets()
+    }
+}
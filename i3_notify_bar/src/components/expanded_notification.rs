@@ -0,0 +1,199 @@
+use i3_bar_components::{
+    components::{prelude::*, Button, Label},
+    protocol::ClickEvent,
+    string::AnimatedString,
+    ManageComponents,
+};
+use notify_server::NotificationId;
+
+use crate::{
+    icons,
+    notification_bar::{InvokeAction as _, NotificationData, NotificationManagerCommands},
+};
+
+use super::history_list::format_time;
+
+/// Layer showing all details of a single notification.
+/// The body is split across several blocks instead of scrolling.
+pub struct ExpandedNotification {
+    header: Label<String>,
+    summary: Label<AnimatedString>,
+    body: Vec<Label<String>>,
+    action_buttons: Vec<(Button, String)>,
+    close_btn: Button,
+    id: NotificationId,
+    notification_manager_cmd: NotificationManagerCommands,
+}
+
+impl ExpandedNotification {
+    pub fn new(
+        nd: &NotificationData,
+        max_width: usize,
+        move_chars_per_sec: usize,
+        notification_manager_cmd: NotificationManagerCommands,
+    ) -> Self {
+        let icon = if nd.icon != ' ' {
+            format!("{} ", nd.icon)
+        } else {
+            String::new()
+        };
+        let mut header = Label::new(format!(" {icon}{} {} ", nd.app_name, format_time(nd.time)));
+        let mut summary = Label::new(
+            AnimatedString::new(format!(" {} ", nd.summary))
+                .with_max_width(max_width)
+                .with_move_chars_per_sec(move_chars_per_sec),
+        );
+        let mut body = split_text(&nd.body, max_width)
+            .into_iter()
+            .map(|line| Label::new(format!(" {line} ")))
+            .collect::<Vec<_>>();
+        nd.style.iter().for_each(|s| {
+            s.apply(&mut header);
+            s.apply(&mut summary);
+            body.iter_mut().for_each(|l| s.apply(l));
+        });
+
+        let action_buttons = nd
+            .actions
+            .iter()
+            .map(|a| {
+                (
+                    Button::new(Box::new(format!(" {} ", a.text))),
+                    a.key.clone(),
+                )
+            })
+            .collect();
+        let btn_text = format!(
+            " {} ",
+            icons::get_icon("close").map_or("close".to_owned(), |i| i.to_string())
+        );
+
+        Self {
+            header,
+            summary,
+            body,
+            action_buttons,
+            close_btn: Button::new(Box::new(btn_text)),
+            id: nd.id,
+            notification_manager_cmd,
+        }
+    }
+}
+
+impl Component for ExpandedNotification {
+    fn update(&mut self, dt: f64) {
+        self.header.update(dt);
+        self.summary.update(dt);
+        self.body.iter_mut().for_each(|l| l.update(dt));
+        self.action_buttons
+            .iter_mut()
+            .for_each(|(b, _)| b.update(dt));
+        self.close_btn.update(dt);
+    }
+
+    fn all_properties<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = &'a i3_bar_components::property::Properties> + 'a> {
+        Box::new(
+            self.header
+                .all_properties()
+                .chain(self.summary.all_properties())
+                .chain(self.body.iter().flat_map(Component::all_properties))
+                .chain(self.action_buttons.iter().map(|(b, _)| b.properties()))
+                .chain(self.close_btn.all_properties()),
+        )
+    }
+
+    fn event_targets<'a>(
+        &'a self,
+    ) -> Box<
+        dyn Iterator<
+                Item = (
+                    i3_bar_components::property::Instance,
+                    *const dyn EventTarget,
+                ),
+            > + 'a,
+    > {
+        Box::new(
+            self.action_buttons
+                .iter()
+                .map(|(b, _)| (b.instance(), self as *const _))
+                .chain(std::iter::once((
+                    self.close_btn.instance(),
+                    self as *const _,
+                ))),
+        )
+    }
+}
+
+impl EventTarget for ExpandedNotification {
+    fn event(&mut self, mc: &mut dyn ManageComponents, event: &ClickEvent) {
+        let Some(event_element) = event.get_instance() else {
+            return;
+        };
+        if event.get_button() != 1 {
+            return;
+        }
+
+        if self.close_btn.instance() == event_element {
+            mc.pop_layer();
+            return;
+        }
+
+        let action = self
+            .action_buttons
+            .iter()
+            .find(|(b, _)| b.instance() == event_element);
+
+        if let Some((_, key)) = action {
+            self.notification_manager_cmd.action_invoked(self.id, key);
+            mc.pop_layer();
+        }
+    }
+}
+
+/// Splits `text` into lines of at most `width` chars. Lines are broken at whitespace if possible.
+fn split_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            let mut word = word.chars().collect::<Vec<_>>();
+            if !line.is_empty() && line.chars().count() + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            // Words longer than a line are split
+            while line.chars().count() + word.len() > width {
+                let rest = word.split_off(width - line.chars().count());
+                line.extend(word);
+                lines.push(std::mem::take(&mut line));
+                word = rest;
+            }
+            line.extend(word);
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::split_text;
+
+    #[test]
+    fn split_text_at_whitespace() {
+        assert_eq!(
+            split_text("Your code is 123456\nvalid for 5 minutes", 12),
+            ["Your code is", "123456", "valid for 5", "minutes"]
+        );
+        assert_eq!(split_text("abcdefghij", 4), ["abcd", "efgh", "ij"]);
+        assert_eq!(split_text("ab cdefghij", 4), ["ab", "cdef", "ghij"]);
+        assert!(split_text("", 4).is_empty());
+    }
+}
@@ -0,0 +1,176 @@
+use chrono::{LocalResult, TimeZone};
+use i3_bar_components::{
+    components::{prelude::*, Button, Label},
+    protocol::ClickEvent,
+    string::{AnimatedString, PartiallyAnimatedString},
+    ManageComponents,
+};
+
+use crate::{
+    history::{HistoryEntry, NotificationHistory},
+    icons,
+    notification_bar::NotificationManagerCommands,
+};
+
+use super::action_bar::ActionBar;
+
+/// Layer listing closed notifications. Clicking an entry opens its actions and copy buttons.
+pub struct HistoryList {
+    entries: Vec<HistoryListEntry>,
+    empty_label: Option<Label<String>>,
+    close_btn: Button,
+    notification_manager_cmd: NotificationManagerCommands,
+}
+
+struct HistoryListEntry {
+    label: Label<PartiallyAnimatedString>,
+    entry: HistoryEntry,
+}
+
+impl HistoryList {
+    pub fn new(
+        history: &NotificationHistory,
+        notification_manager_cmd: NotificationManagerCommands,
+        max_width: usize,
+        move_chars_per_sec: usize,
+    ) -> Self {
+        let entries = history
+            .iter()
+            .map(|entry| {
+                let icon = if entry.icon != ' ' {
+                    format!("{} ", entry.icon)
+                } else {
+                    String::new()
+                };
+                let text = PartiallyAnimatedString::new(
+                    Some(format!(" {} {icon}", format_time(entry.time))),
+                    AnimatedString::new(entry.text.clone())
+                        .with_max_width(max_width)
+                        .with_move_chars_per_sec(move_chars_per_sec),
+                    Some(String::from(" ")),
+                );
+                HistoryListEntry {
+                    label: Label::new(text),
+                    entry: entry.clone(),
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let empty_label = if entries.is_empty() {
+            Some(Label::new(String::from(" No notifications ")))
+        } else {
+            None
+        };
+
+        let btn_text = format!(
+            " {} ",
+            icons::get_icon("close").map_or("close".to_owned(), |i| i.to_string())
+        );
+
+        Self {
+            entries,
+            empty_label,
+            close_btn: Button::new(Box::new(btn_text)),
+            notification_manager_cmd,
+        }
+    }
+}
+
+impl Component for HistoryList {
+    fn all_properties<'a>(
+        &'a self,
+    ) -> Box<dyn Iterator<Item = &'a i3_bar_components::property::Properties> + 'a> {
+        Box::new(
+            self.entries
+                .iter()
+                .flat_map(|e| e.label.all_properties())
+                .chain(self.empty_label.iter().flat_map(Component::all_properties))
+                .chain(self.close_btn.all_properties()),
+        )
+    }
+
+    fn update(&mut self, dt: f64) {
+        self.entries.iter_mut().for_each(|e| e.label.update(dt));
+        if let Some(label) = self.empty_label.as_mut() {
+            label.update(dt);
+        }
+        self.close_btn.update(dt);
+    }
+
+    fn event_targets<'a>(
+        &'a self,
+    ) -> Box<
+        dyn Iterator<
+                Item = (
+                    i3_bar_components::property::Instance,
+                    *const dyn EventTarget,
+                ),
+            > + 'a,
+    > {
+        Box::new(
+            self.entries
+                .iter()
+                .map(|e| (e.label.instance(), self as *const _))
+                .chain(std::iter::once((
+                    self.close_btn.instance(),
+                    self as *const _,
+                ))),
+        )
+    }
+}
+
+impl EventTarget for HistoryList {
+    fn event(&mut self, mc: &mut dyn ManageComponents, event: &ClickEvent) {
+        let Some(event_element) = event.get_instance() else {
+            return;
+        };
+        if event.get_button() != 1 {
+            return;
+        }
+
+        if self.close_btn.instance() == event_element {
+            mc.pop_layer();
+            return;
+        }
+
+        let Some(entry) = self
+            .entries
+            .iter()
+            .find(|e| e.label.instance() == event_element)
+        else {
+            return;
+        };
+        // Actions can only be invoked while the sending application is still running
+        let actions = match entry.entry.sender.as_deref() {
+            Some(sender)
+                if !entry.entry.actions.is_empty() && notify_server::is_connected(sender) =>
+            {
+                &entry.entry.actions[..]
+            }
+            _ => &[],
+        };
+        mc.new_layer();
+        mc.add_component(Box::new(
+            ActionBar::new(
+                actions,
+                entry.entry.id,
+                self.notification_manager_cmd.clone(),
+            )
+            .with_copy([
+                ("summary".to_owned(), entry.entry.summary.clone()),
+                ("body".to_owned(), entry.entry.body.clone()),
+            ]),
+        ));
+    }
+}
+
+pub(super) fn format_time(time: i64) -> String {
+    let LocalResult::Single(time) = chrono::Local.timestamp_opt(time, 0) else {
+        return String::from("--:--");
+    };
+    if time.date_naive() == chrono::Local::now().date_naive() {
+        time.format("%H:%M").to_string()
+    } else {
+        time.format("%d.%m. %H:%M").to_string()
+    }
+}
@@ -2,9 +2,9 @@ use std::sync::{Arc, RwLock};
 
 use i3_bar_components::components::{Button, ButtonGroup, GroupButton, Label};
 
-use crate::notification_bar::MinimalUrgency;
+use crate::notification_bar::{MinimalUrgency, SuppressMode};
 
-const BUTTON_CONFIG: [ButtonConfig; 4] = [
+const BUTTON_CONFIG: [ButtonConfig<MinimalUrgency>; 4] = [
     ButtonConfig {
         color: "#00FF00",
         text: " low ",
@@ -42,14 +42,42 @@ pub fn init(selected: Arc<RwLock<MinimalUrgency>>) -> ButtonGroup<MinimalUrgency
     ButtonGroup::new(buttons, selected, Some(description))
 }
 
-struct ButtonConfig<'a> {
+const SUPPRESS_MODE_BUTTON_CONFIG: [ButtonConfig<SuppressMode>; 2] = [
+    ButtonConfig {
+        color: "#FFFFFF",
+        text: " queue ",
+        key: SuppressMode::Queue,
+    },
+    ButtonConfig {
+        color: "#FFFFFF",
+        text: " drop ",
+        key: SuppressMode::Drop,
+    },
+];
+
+/// Selects whether notifications below the minimal urgency are queued or dropped
+pub fn init_suppress_mode(
+    selected: Arc<RwLock<SuppressMode>>,
+) -> ButtonGroup<SuppressMode, String> {
+    let buttons = SUPPRESS_MODE_BUTTON_CONFIG
+        .iter()
+        .enumerate()
+        .map(|(pos, config)| GroupButton::new(pos as isize, config.key, Button::from(config)))
+        .collect();
+
+    let description = Label::new("Suppressed".to_string());
+
+    ButtonGroup::new(buttons, selected, Some(description))
+}
+
+struct ButtonConfig<'a, K = MinimalUrgency> {
     text: &'a str,
     color: &'a str,
-    key: MinimalUrgency,
+    key: K,
 }
 
-impl<'a> From<&'a ButtonConfig<'a>> for Button {
-    fn from(config: &'a ButtonConfig) -> Self {
+impl<'a, K> From<&'a ButtonConfig<'a, K>> for Button {
+    fn from(config: &'a ButtonConfig<K>) -> Self {
         use i3_bar_components::components::prelude::*;
         let mut button = Button::new(Box::new(config.text.to_owned()));
         button.set_color_text(Some(config.color.to_owned()));
@@ -1,10 +1,14 @@
 mod action_bar;
 mod close_type;
+mod config_error;
+mod expanded_notification;
+mod history_list;
 mod min_urgency_selector;
 mod notification;
 mod notification_bar;
 mod notification_group;
 
+pub use config_error::ConfigError;
 pub use notification::NotificationComponent;
-pub use notification_bar::NotificationBar;
+pub use notification_bar::{MenuState, NotificationBar};
 pub use notification_group::NotificationGroup;
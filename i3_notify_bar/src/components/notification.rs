@@ -4,27 +4,35 @@ use std::{
 };
 
 use i3_bar_components::{
-    components::{prelude::*, Button, Label, ProgressBar},
+    components::{
+        prelude::{Markup as _, *},
+        Button, HorizontalProgressBar, Label, ProgressBar,
+    },
+    property,
     protocol::ClickEvent,
-    string::{AnimatedString, PartiallyAnimatedString},
+    string::{AnimatedString, ComponentString, PartiallyAnimatedString},
     ManageComponents,
 };
 use log::debug;
 use notify_server::{notification::Action, CloseReason, NotificationId};
 
 use crate::{
-    icons,
+    clipboard, exec, icons,
+    markup::{AnimatedMarkup, MarkupMode},
     notification_bar::{
         CloseNotification, InvokeAction as _, NotificationData, NotificationManagerCommands,
     },
-    rule::Style,
+    rule::{ClickBehaviour, Style},
 };
 
-use super::action_bar::ActionBar;
+use super::{action_bar::ActionBar, expanded_notification::ExpandedNotification};
+
+const VALUE_BAR_WIDTH: usize = 10;
 
 pub struct NotificationComponent {
     notification: Arc<RwLock<NotificationData>>,
-    label: Label<PartiallyAnimatedString>,
+    label: Label,
+    value_bar: Option<HorizontalProgressBar>,
     close_button: Button,
     close_timer: Option<ProgressBar>,
     name: String,
@@ -33,6 +41,9 @@ pub struct NotificationComponent {
     max_width: usize,
     move_chars_per_sec: usize,
     notification_state_id: usize,
+    icon: char,
+    text: String,
+    style: Vec<Style>,
 }
 
 impl NotificationComponent {
@@ -59,11 +70,18 @@ impl NotificationComponent {
 
         label.set_show(true);
         label.set_block_width(Some(0));
+        if nd_l.markup_mode == MarkupMode::Pango {
+            label.set_markup(property::Markup::Pango);
+        }
 
         nd_l.style.iter().for_each(|s| {
             s.apply(&mut label);
         });
 
+        let value_bar = nd_l
+            .value
+            .map(|value| create_value_bar(nd_l.style.as_slice(), value));
+
         let name = notification_id_to_notification_compnent_name(nd_l.id);
         let close_button = create_button(
             nd_l.style.as_slice(),
@@ -73,42 +91,83 @@ impl NotificationComponent {
         );
         let actions = nd_l.actions.clone();
         let notification_state_id = nd_l.notification_update_id;
+        let icon = nd_l.icon;
+        let text = nd_l.text.clone();
+        let style = nd_l.style.clone();
         drop(nd_l);
         Self {
             notification: nd,
             close_button,
             name,
             label,
+            value_bar,
             close_timer,
             notification_manager_cmd,
             actions,
             max_width,
             move_chars_per_sec,
             notification_state_id,
+            icon,
+            text,
+            style,
         }
     }
 
     fn reinit(&mut self) {
-        let new = Self::new(
+        let mut new = Self::new(
             Arc::clone(&self.notification),
             self.max_width,
             self.move_chars_per_sec,
             self.notification_manager_cmd.clone(),
         );
+        // Notifications reporting progress replace themselves frequently.
+        // Keeping the label prevents the text animation from restarting on every update.
+        if new.icon == self.icon && new.text == self.text && new.style == self.style {
+            std::mem::swap(&mut new.label, &mut self.label);
+        }
         *self = new;
     }
 
     fn on_notification_right_click(&mut self, mc: &mut dyn ManageComponents) {
+        let nd = self
+            .notification
+            .read()
+            .expect("Unable to read notification");
+        let copy = copy_entries(&nd);
+        let links = nd.links.clone();
+        drop(nd);
         mc.new_layer();
-        mc.add_component(Box::new(ActionBar::new(
-            &self.actions,
-            self.id(),
+        mc.add_component(Box::new(
+            ActionBar::new(
+                &self.actions,
+                self.id(),
+                self.notification_manager_cmd.clone(),
+            )
+            .with_links(&links)
+            .with_copy(copy),
+        ))
+    }
+
+    fn expand(&self, mc: &mut dyn ManageComponents) {
+        let expanded = ExpandedNotification::new(
+            &self
+                .notification
+                .read()
+                .expect("Unable to read notification"),
+            self.max_width,
+            self.move_chars_per_sec,
             self.notification_manager_cmd.clone(),
-        )))
+        );
+        mc.new_layer();
+        mc.add_component(Box::new(expanded));
     }
 
     fn on_notification_click(&mut self) {
-        let action = self.actions.iter().find(|action| action.key == "default");
+        self.invoke_action("default");
+    }
+
+    fn invoke_action(&self, key: &str) {
+        let action = self.actions.iter().find(|action| action.key == key);
 
         if let Some(action) = action {
             self.notification_manager_cmd
@@ -116,6 +175,41 @@ impl NotificationComponent {
         }
     }
 
+    fn dismiss(&self, mc: &mut dyn ManageComponents) {
+        let id = self.id();
+        debug!("Notification({id}) dismissed");
+        self.notification_manager_cmd
+            .notification_closed(id, CloseReason::Dismissed);
+        mc.remove_by_name(&self.name);
+    }
+
+    fn on_click_behaviour(&mut self, mc: &mut dyn ManageComponents, behaviour: ClickBehaviour) {
+        match behaviour {
+            ClickBehaviour::InvokeAction(key) => self.invoke_action(&key),
+            ClickBehaviour::Dismiss => self.dismiss(mc),
+            ClickBehaviour::ActionBar => self.on_notification_right_click(mc),
+            ClickBehaviour::Copy(source) => {
+                let text = source.text(
+                    &self
+                        .notification
+                        .read()
+                        .expect("Unable to read notification"),
+                );
+                if let Some(text) = text {
+                    clipboard::copy(&text)
+                }
+            }
+            ClickBehaviour::Expand => self.expand(mc),
+            ClickBehaviour::Exec(command) => exec::spawn(
+                &command,
+                &self
+                    .notification
+                    .read()
+                    .expect("Unable to read notification"),
+            ),
+        }
+    }
+
     pub fn id(&self) -> NotificationId {
         self.notification
             .read()
@@ -131,6 +225,7 @@ impl Component for NotificationComponent {
         Box::new(
             [
                 Some(self.label.all_properties()),
+                self.value_bar.as_ref().map(Component::all_properties),
                 self.close_timer.as_ref().map(Component::all_properties),
                 Some(self.close_button.all_properties()),
             ]
@@ -150,6 +245,9 @@ impl Component for NotificationComponent {
         };
 
         self.label.update(dt);
+        if let Some(value_bar) = self.value_bar.as_mut() {
+            value_bar.update(dt);
+        }
         self.close_button.update(dt);
         if let Some(t) = self.close_timer.as_mut() {
             let n = self
@@ -189,9 +287,22 @@ impl Component for NotificationComponent {
 
 impl EventTarget for NotificationComponent {
     fn event(&mut self, mc: &mut dyn ManageComponents, event: &ClickEvent) {
+        let modifiers = event.get_modifiers().as_deref().unwrap_or_default();
+        let behaviour = self
+            .notification
+            .read()
+            .expect("Unable to read notification")
+            .click_behaviour(event.get_button(), modifiers)
+            .cloned();
+        if let Some(behaviour) = behaviour {
+            self.on_click_behaviour(mc, behaviour);
+            return;
+        }
         match event.get_button() {
             // Notification clicked
             1 => self.on_notification_click(),
+            // Notification middle click
+            2 => self.expand(mc),
             // Notification right click
             3 => self.on_notification_right_click(mc),
             _ => {}
@@ -199,6 +310,23 @@ impl EventTarget for NotificationComponent {
     }
 }
 
+/// Summary, body and the captures of all regex copy bindings that match
+fn copy_entries(nd: &NotificationData) -> Vec<(String, String)> {
+    let captures = nd.on_click.iter().filter_map(|b| match &b.behaviour {
+        ClickBehaviour::Copy(source) if source.regex.is_some() => {
+            source.text(nd).map(|text| (text.clone(), text))
+        }
+        _ => None,
+    });
+    let mut entries = vec![
+        ("summary".to_owned(), nd.summary.clone()),
+        ("body".to_owned(), nd.body.clone()),
+    ];
+    entries.extend(captures);
+    entries.dedup();
+    entries
+}
+
 fn create_button(
     style: &[Style],
     id: NotificationId,
@@ -219,6 +347,17 @@ fn create_button(
     b
 }
 
+fn create_value_bar(style: &[Style], value: u8) -> HorizontalProgressBar {
+    let mut bar = HorizontalProgressBar::new(100., VALUE_BAR_WIDTH);
+    bar.set_current(value.into());
+    bar.set_show(false);
+    bar.set_block_width(Some(0));
+    style.iter().for_each(|s| {
+        s.apply(&mut bar);
+    });
+    bar
+}
+
 fn create_timer(style: &[Style], expire: f64) -> ProgressBar {
     let mut t = ProgressBar::new(expire);
     t.set_show(false);
@@ -237,17 +376,26 @@ pub fn notification_data_to_animated_text(
     nd: &NotificationData,
     max_width: usize,
     move_chars_per_sec: usize,
-) -> PartiallyAnimatedString {
+) -> Box<dyn ComponentString> {
     let icon = if nd.icon != ' ' {
         Some(format!("{} ", nd.icon))
     } else {
         None
     };
-    PartiallyAnimatedString::new(
+    if nd.markup_mode == MarkupMode::Pango {
+        return Box::new(AnimatedMarkup::new(
+            icon,
+            nd.text.clone(),
+            max_width,
+            move_chars_per_sec,
+            Some(String::from(" ")),
+        ));
+    }
+    Box::new(PartiallyAnimatedString::new(
         icon,
         AnimatedString::new(nd.text.clone())
             .with_max_width(max_width)
             .with_move_chars_per_sec(move_chars_per_sec),
         String::from(" ").into(),
-    )
+    ))
 }
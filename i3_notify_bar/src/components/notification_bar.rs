@@ -2,25 +2,38 @@ use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
 
 use i3_bar_components::{
-    components::{prelude::*, Button},
+    components::{prelude::*, Button, Label},
     protocol::ClickEvent,
     ManageComponents,
 };
 
+use crate::history::NotificationHistory;
 use crate::notification_bar::{
     CloseAllNotifications as _, NotificationEvent, NotificationManagerCommands,
 };
 use crate::SystemCommand;
 use crate::{
     icons,
-    notification_bar::{MinimalUrgency, NotificationData},
+    notification_bar::{MinimalUrgency, NotificationData, SuppressMode},
 };
 
-use super::{min_urgency_selector, NotificationComponent, NotificationGroup};
+use super::{
+    history_list::HistoryList, min_urgency_selector, NotificationComponent, NotificationGroup,
+};
+
+/// State shared between the menu and the notification manager
+#[derive(Clone)]
+pub struct MenuState {
+    pub minimal_urgency: Arc<RwLock<MinimalUrgency>>,
+    pub suppress_mode: Arc<RwLock<SuppressMode>>,
+    pub history: Arc<RwLock<NotificationHistory>>,
+}
 
 pub struct NotificationBar {
     notifications: Vec<NotificationComponent>,
     groups: BTreeMap<String, NotificationGroup>,
+    /// Shows the number of queued notifications
+    queued_label: Option<Label<String>>,
     menu_btn: Button,
     notification_manager_cmd: NotificationManagerCommands,
     max_width: usize,
@@ -31,7 +44,7 @@ pub struct NotificationBar {
 
 impl NotificationBar {
     pub fn new(
-        selected_urgency: Arc<RwLock<MinimalUrgency>>,
+        menu_state: MenuState,
         notification_manager_cmd: NotificationManagerCommands,
         notification_event_channel: std::sync::mpsc::Receiver<NotificationEvent>,
         max_width: usize,
@@ -52,12 +65,20 @@ impl NotificationBar {
                 return;
             }
             system_command_tx_menu_btn.send(SystemCommand::ForceUpdate);
-            open_menu(mc, ce, selected_urgency.clone(), nm_cmd.clone());
+            open_menu(
+                mc,
+                ce,
+                menu_state.clone(),
+                nm_cmd.clone(),
+                max_width,
+                animation_chars_per_second,
+            );
         });
 
         Self {
             notifications: Vec::default(),
             groups: BTreeMap::default(),
+            queued_label: None,
             menu_btn,
             notification_manager_cmd,
             notification_event_channel,
@@ -78,6 +99,7 @@ impl Component for NotificationBar {
                 .map(Component::all_properties)
                 .chain(self.groups.values().map(Component::all_properties))
                 .flatten()
+                .chain(self.queued_label.iter().flat_map(Component::all_properties))
                 .chain(self.menu_btn.all_properties()),
         )
     }
@@ -97,6 +119,8 @@ impl Component for NotificationBar {
                         self.animation_chars_per_second,
                     ),
                     Remove(n) => remove_notification(n, &mut self.groups, &mut self.notifications),
+                    Queued(0) => self.queued_label = None,
+                    Queued(count) => self.queued_label = Some(queued_label(count)),
                 }
             });
 
@@ -110,6 +134,9 @@ impl Component for NotificationBar {
             )
             .for_each(|c| c.update(dt));
         self.groups.retain(|_, g| !g.is_empty());
+        if let Some(label) = self.queued_label.as_mut() {
+            label.update(dt);
+        }
         self.menu_btn.update(dt);
     }
 
@@ -131,6 +158,11 @@ impl Component for NotificationBar {
     }
 }
 
+fn queued_label(count: usize) -> Label<String> {
+    let icon = icons::get_icon("queued").map_or(String::from("queued"), String::from);
+    Label::new(format!(" {icon} {count} "))
+}
+
 fn add_notification(
     n: Arc<RwLock<NotificationData>>,
     groups: &mut BTreeMap<String, NotificationGroup>,
@@ -183,13 +215,37 @@ fn remove_notification(
 fn open_menu(
     mc: &mut dyn ManageComponents,
     ce: &ClickEvent,
-    selected: Arc<RwLock<MinimalUrgency>>,
+    menu_state: MenuState,
     notification_manager_cmd: NotificationManagerCommands,
+    max_width: usize,
+    animation_chars_per_second: usize,
 ) {
     if ce.get_button() != 1 {
         return;
     };
+    let MenuState {
+        minimal_urgency,
+        suppress_mode,
+        history,
+    } = menu_state;
     mc.new_layer();
+    let history_icon =
+        icons::get_icon("history").map_or(String::from(" history "), |c| format!(" {} ", c));
+    let mut history_btn = Button::new(Box::new(history_icon));
+    let history_nm_cmd = notification_manager_cmd.clone();
+    history_btn.set_on_click(move |_, mc, ce| {
+        if ce.get_button() != 1 {
+            return;
+        };
+        let history = history.read().unwrap_or_else(|e| e.into_inner());
+        mc.new_layer();
+        mc.add_component(Box::new(HistoryList::new(
+            &history,
+            history_nm_cmd.clone(),
+            max_width,
+            animation_chars_per_second,
+        )));
+    });
     let mut close_all = Button::new(Box::new(" close all ".to_owned()));
     close_all.set_on_click(move |_, _, ce| {
         log::debug!("close button clicked");
@@ -198,9 +254,12 @@ fn open_menu(
         };
         notification_manager_cmd.close_all_notifications(notify_server::CloseReason::Dismissed);
     });
-    let group = min_urgency_selector::init(selected);
+    let group = min_urgency_selector::init(minimal_urgency);
+    let suppress_mode_group = min_urgency_selector::init_suppress_mode(suppress_mode);
     mc.add_component(Box::new(close_all));
+    mc.add_component(Box::new(history_btn));
     mc.add_component(Box::new(group));
+    mc.add_component(Box::new(suppress_mode_group));
     mc.add_component(Box::new(menu_button_close()));
 }
 
@@ -3,14 +3,24 @@ use std::fmt::{Display, Formatter};
 use std::io::BufRead;
 use std::str::FromStr;
 
+use chrono::{NaiveTime, Weekday};
 use log::{error, info};
-use pest::{iterators::Pair, Parser};
+use pest::{
+    error::LineColLocation,
+    iterators::{Pair, Pairs},
+    Parser, Span,
+};
 use regex::Regex;
 
 use crate::rule::NumberCondition;
 use crate::{
     icons,
-    rule::{Action, ConditionTypeString, Conditions as Condition, Definition, SetProperty, Style},
+    notification_bar::MinimalUrgency,
+    rule::{
+        normalize_modifier, Action, ClickBehaviour, ClickBinding, ConditionTypeString,
+        Conditions as Condition, CopyField, CopySource, Definition, SetProperty, Style,
+    },
+    schedule::ScheduleEntry,
     template,
 };
 use emoji::EmojiMode;
@@ -19,36 +29,121 @@ use emoji::EmojiMode;
 #[grammar = "config.pest"]
 struct ConfigParser;
 
-pub fn parse_config(config: &mut dyn BufRead) -> ParseResult<Vec<Definition>> {
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub definitions: Vec<Definition>,
+    pub schedule: Vec<ScheduleEntry>,
+}
+
+/// Parses the config. If it contains errors, the error contains all valid
+/// rules and schedule entries so they can still be used.
+pub fn parse_config(config: &mut dyn BufRead) -> Result<Config, InvalidConfig> {
     info!("Reading conditions");
-    let config = config
+    let mut lines = config
         .lines()
         .map(unwrap_line)
         .map(|mut line| {
             line.push('\n');
             line
         })
-        .collect::<String>();
+        .collect::<Vec<_>>();
+
+    let mut errors = Vec::new();
+    let config = loop {
+        let input = lines.concat();
+        let error = match ConfigParser::parse(Rule::config, &input) {
+            Ok(mut pairs) => break parse_config_pairs(pairs.next(), &mut errors),
+            Err(e) => ParseError::from(e),
+        };
+        // Skip the broken block and try again to find errors in the remaining blocks
+        let skipped = skip_block(&mut lines, error.line);
+        errors.push(error);
+        if !skipped {
+            break Config::default();
+        }
+    };
 
-    let config = ConfigParser::parse(Rule::config, &config);
-    let config = match config {
-        Ok(config) => config,
-        Err(e) => return Err(ParseError::PestError(Box::new(e))),
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        errors.sort_by_key(|e| (e.line, e.column));
+        Err(InvalidConfig { config, errors })
     }
-    .next();
+}
 
-    let config = match config {
-        Some(c) => c,
-        None => return Err(ParseError::UnexpectedEnd),
+fn parse_config_pairs(config: Option<Pair<Rule>>, errors: &mut Vec<ParseError>) -> Config {
+    let mut parsed = Config::default();
+    let Some(config) = config else {
+        return parsed;
     };
+    for pair in config.into_inner() {
+        match pair.as_rule() {
+            Rule::definition => match parse_definition(pair) {
+                Ok(definition) => parsed.definitions.push(definition),
+                Err(mut e) => errors.append(&mut e),
+            },
+            Rule::schedule => match parse_schedule(pair) {
+                Ok(mut schedule) => parsed.schedule.append(&mut schedule),
+                Err(mut e) => errors.append(&mut e),
+            },
+            Rule::EOI => {}
+            rule => unreachable!("Unexpected rule: {:#?}", rule),
+        }
+    }
+    parsed
+}
 
-    let definitions = config.into_inner().filter(|def| match def.as_rule() {
-        Rule::definition => true,
-        Rule::EOI => false,
-        rule => unreachable!("Unexpected rule: {:#?}", rule),
-    });
-
-    definitions.map(parse_definition).collect::<Result<_, _>>()
+/// Blanks the block causing an error at `line` (1 based). Line numbers of the following blocks stay the same.
+/// A line starting with `rule` or `schedule` without indentation starts a new top level block. A missing `end`
+/// is reported at a later line, so the first block before the error with unbalanced `end`s is removed
+/// instead of the block containing the error. Returns false if there was nothing left to skip.
+fn skip_block(lines: &mut [String], line: usize) -> bool {
+    let error_line = line.saturating_sub(1).min(lines.len().saturating_sub(1));
+    let mut starts = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, l)| matches!(l.trim_end(), "rule" | "schedule"))
+        .map(|(i, _)| i)
+        .collect::<Vec<_>>();
+    if starts.first() != Some(&0) {
+        starts.insert(0, 0);
+    }
+    let blocks = starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| *start..starts.get(i + 1).copied().unwrap_or(lines.len()))
+        .take_while(|block| block.start <= error_line)
+        .collect::<Vec<_>>();
+    let is_balanced = |block: &std::ops::Range<usize>| {
+        let mut depth = 0isize;
+        for l in &lines[block.clone()] {
+            match l.trim() {
+                "rule" | "condition" | "action" | "style" | "on_click" | "schedule" => depth += 1,
+                "end" => depth -= 1,
+                _ => {}
+            }
+            if depth < 0 {
+                return false;
+            }
+        }
+        depth == 0
+    };
+    let Some(block) = blocks
+        .iter()
+        .find(|block| !is_balanced(block))
+        .or(blocks.last())
+        .cloned()
+    else {
+        return false;
+    };
+    let mut skipped = false;
+    for l in &mut lines[block] {
+        if !l.trim().is_empty() {
+            *l = String::from("\n");
+            skipped = true;
+        }
+    }
+    skipped
 }
 
 fn unwrap_line(result: Result<String, std::io::Error>) -> String {
@@ -61,39 +156,76 @@ fn unwrap_line(result: Result<String, std::io::Error>) -> String {
     }
 }
 
-fn parse_definition(definition: Pair<Rule>) -> ParseResult<Definition> {
+/// Returns the next pair or an error at the end of `span`
+fn next<'i>(pairs: &mut Pairs<'i, Rule>, span: Span) -> ParseResult<Pair<'i, Rule>> {
+    pairs
+        .next()
+        .ok_or_else(|| ParseError::at_end(ParseErrorKind::UnexpectedEnd, span))
+}
+
+/// Returns the first inner pair of `pair`
+fn first_inner(pair: Pair<Rule>) -> ParseResult<Pair<Rule>> {
+    let span = pair.as_span();
+    next(&mut pair.into_inner(), span)
+}
+
+/// Collects all values or all errors
+fn collect_all<T>(results: impl Iterator<Item = ParseResult<T>>) -> ParseResults<Vec<T>> {
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for result in results {
+        match result {
+            Ok(v) => values.push(v),
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Parses a rule. Errors in sections and sub rules are collected, the rule is only valid if all of them are.
+fn parse_definition(definition: Pair<Rule>) -> ParseResults<Definition> {
     let mut def = Definition::default();
-    let groups = definition.into_inner();
-    for section in groups {
-        let section = section
-            .into_inner()
-            .next()
-            .ok_or(ParseError::UnexpectedEnd)?;
-        match section.as_rule() {
-            Rule::condition_section => parse_condition_section(section, &mut def.conditions)?,
-            Rule::style_section => def.style = parse_style_section(section)?,
-            Rule::action_section => def.actions = parse_action_section(section)?,
-            Rule::definition => def.sub_definition.push(parse_definition(section)?),
+    let mut errors = Vec::new();
+    for section in definition.into_inner() {
+        let section = match first_inner(section) {
+            Ok(s) => s,
+            Err(e) => {
+                errors.push(e);
+                continue;
+            }
+        };
+        let result = match section.as_rule() {
+            Rule::condition_section => parse_condition_section(section, &mut def.conditions),
+            Rule::style_section => parse_style_section(section).map(|s| def.style = s),
+            Rule::action_section => parse_action_section(section).map(|a| def.actions = a),
+            Rule::on_click_section => parse_on_click_section(section).map(|b| def.on_click = b),
+            Rule::definition => parse_definition(section).map(|d| def.sub_definition.push(d)),
             _ => unreachable!(),
+        };
+        if let Err(mut e) = result {
+            errors.append(&mut e);
         }
     }
-    Ok(def)
+    if errors.is_empty() {
+        Ok(def)
+    } else {
+        Err(errors)
+    }
 }
 
-fn parse_action_section(action_section: Pair<Rule>) -> ParseResult<Vec<Action>> {
-    action_section
-        .into_inner()
-        .map(parse_action)
-        .collect::<Result<_, _>>()
+fn parse_action_section(action_section: Pair<Rule>) -> ParseResults<Vec<Action>> {
+    collect_all(action_section.into_inner().map(parse_action))
 }
 
 fn parse_action(action: Pair<Rule>) -> ParseResult<Action> {
-    let action = action
-        .into_inner()
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?;
+    let action = first_inner(action)?;
     match action.as_rule() {
         Rule::set_action => parse_set_action(action),
+        Rule::exec_action => parse_exec_action(action),
         Rule::stop_action => Ok(Action::Stop),
         Rule::ignore_action => Ok(Action::Ignore),
         _ => unreachable!(),
@@ -101,28 +233,30 @@ fn parse_action(action: Pair<Rule>) -> ParseResult<Action> {
 }
 
 fn parse_set_action(set_action: Pair<Rule>) -> ParseResult<Action> {
+    let span = set_action.as_span();
     let mut inner = set_action.into_inner();
-    let key = inner
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?
-        .into_inner()
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?;
-    let value = inner.next().ok_or(ParseError::UnexpectedEnd)?.as_str();
+    let key = first_inner(next(&mut inner, span)?)?;
+    let value_pair = next(&mut inner, span)?;
+    let value = value_pair.as_str();
 
     let action = match key.as_rule() {
-        Rule::app_icon => Action::Set(SetProperty::Icon(icons::get_icon(value).unwrap_or('\u{0}'))),
+        Rule::app_icon => Action::Set(SetProperty::Icon(icons::parse_icon(value).ok_or_else(
+            || ParseError::new(ParseErrorKind::UnknownIcon(value.to_owned()), &value_pair),
+        )?)),
         Rule::text => Action::Set(SetProperty::Text(
             match template::add_template(value.to_owned()) {
                 Ok(id) => id,
                 Err(_) => template::DEFAULT_TEMPLATE_ID,
             },
         )),
-        Rule::expire_timeout => Action::Set(SetProperty::ExpireTimeout(
-            value.parse().map_err(ParseError::NumParse)?,
-        )),
+        Rule::expire_timeout => {
+            Action::Set(SetProperty::ExpireTimeout(value.parse().map_err(|e| {
+                ParseError::new(ParseErrorKind::NumParse(e), &value_pair)
+            })?))
+        }
         Rule::emoji_mode => Action::Set(SetProperty::EmojiMode(
-            EmojiMode::from_str(value).map_err(ParseError::EmojiMode)?,
+            EmojiMode::from_str(value)
+                .map_err(|e| ParseError::new(ParseErrorKind::EmojiMode(e), &value_pair))?,
         )),
         Rule::group => Action::Set(SetProperty::Group(value.to_string())),
         _ => unreachable!(),
@@ -130,28 +264,158 @@ fn parse_set_action(set_action: Pair<Rule>) -> ParseResult<Action> {
     Ok(action)
 }
 
+fn parse_exec_action(exec_action: Pair<Rule>) -> ParseResult<Action> {
+    let command = first_inner(exec_action)?;
+    let args = split_args(command.as_str())
+        .ok_or_else(|| ParseError::new(ParseErrorKind::UnterminatedQuote, &command))?;
+    args.into_iter()
+        .map(|(offset, arg)| {
+            template::add_template(arg).map_err(|_| {
+                let column = command.as_str()[..offset].chars().count();
+                ParseError::at_offset(ParseErrorKind::Template, &command, column)
+            })
+        })
+        .collect::<ParseResult<_>>()
+        .map(Action::Exec)
+}
+
+/// Splits a command into arguments at whitespace. Quotes group words and templates (`{{ ... }}`)
+/// are kept as they are. Every argument is returned with the byte offset it starts at.
+/// Returns `None` if a quote or template is not closed.
+fn split_args(command: &str) -> Option<Vec<(usize, String)>> {
+    let mut args = Vec::new();
+    let mut arg: Option<(usize, String)> = None;
+    let mut chars = command.char_indices().peekable();
+    while let Some((offset, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if let Some(arg) = arg.take() {
+                    args.push(arg);
+                }
+            }
+            '"' | '\'' => {
+                let (_, arg) = arg.get_or_insert_with(|| (offset, String::new()));
+                loop {
+                    match chars.next()?.1 {
+                        q if q == c => break,
+                        '\\' if c == '"' => arg.push(chars.next()?.1),
+                        other => arg.push(other),
+                    }
+                }
+            }
+            '{' if chars.peek().map(|(_, c)| c) == Some(&'{') => {
+                let (_, arg) = arg.get_or_insert_with(|| (offset, String::new()));
+                arg.push(c);
+                let mut last = c;
+                loop {
+                    let (_, next) = chars.next()?;
+                    arg.push(next);
+                    if last == '}' && next == '}' {
+                        break;
+                    }
+                    last = next;
+                }
+            }
+            '\\' => arg
+                .get_or_insert_with(|| (offset, String::new()))
+                .1
+                .push(chars.next()?.1),
+            c => arg.get_or_insert_with(|| (offset, String::new())).1.push(c),
+        }
+    }
+    args.extend(arg);
+    Some(args)
+}
+
+fn parse_on_click_section(
+    on_click_section: Pair<Rule>,
+) -> ParseResults<Vec<ClickBinding<Vec<u64>>>> {
+    collect_all(on_click_section.into_inner().map(parse_click_binding))
+}
+
+fn parse_click_binding(binding: Pair<Rule>) -> ParseResult<ClickBinding<Vec<u64>>> {
+    let mut modifiers = Vec::new();
+    let mut button = 0;
+    let mut behaviour = None;
+    for part in binding.into_inner() {
+        match part.as_rule() {
+            Rule::click_modifier => modifiers.push(normalize_modifier(part.as_str())),
+            Rule::click_button => {
+                button = part
+                    .as_str()
+                    .parse()
+                    .map_err(|e| ParseError::new(ParseErrorKind::NumParse(e), &part))?
+            }
+            Rule::click_behaviour => behaviour = Some(parse_click_behaviour(first_inner(part)?)?),
+            _ => unreachable!(),
+        }
+    }
+    Ok(ClickBinding {
+        button,
+        modifiers,
+        behaviour: behaviour.unwrap_or_else(|| unreachable!()),
+    })
+}
+
+fn parse_click_behaviour(behaviour: Pair<Rule>) -> ParseResult<ClickBehaviour<Vec<u64>>> {
+    Ok(match behaviour.as_rule() {
+        Rule::action_bar_behaviour => ClickBehaviour::ActionBar,
+        Rule::invoke_action_behaviour => {
+            ClickBehaviour::InvokeAction(first_inner(behaviour)?.as_str().trim().to_owned())
+        }
+        Rule::dismiss_behaviour => ClickBehaviour::Dismiss,
+        Rule::copy_behaviour => ClickBehaviour::Copy(parse_copy_source(behaviour)?),
+        Rule::expand_behaviour => ClickBehaviour::Expand,
+        Rule::exec_behaviour => {
+            let Action::Exec(args) = parse_exec_action(behaviour)? else {
+                unreachable!()
+            };
+            ClickBehaviour::Exec(args)
+        }
+        _ => unreachable!(),
+    })
+}
+
+fn parse_copy_source(copy_behaviour: Pair<Rule>) -> ParseResult<CopySource> {
+    let span = copy_behaviour.as_span();
+    let mut inner = copy_behaviour.into_inner();
+    let field = match first_inner(next(&mut inner, span)?)?.as_rule() {
+        Rule::summary => CopyField::Summary,
+        Rule::body => CopyField::Body,
+        _ => unreachable!(),
+    };
+    // Skip `match`
+    let regex = match inner.nth(1) {
+        Some(value) => Some(
+            Regex::new(value.as_str())
+                .map_err(|e| ParseError::new(ParseErrorKind::Regex(e), &value))?,
+        ),
+        None => None,
+    };
+    Ok(CopySource { field, regex })
+}
+
 fn parse_condition_section(
     condition_section: Pair<Rule>,
     conditions: &mut Vec<Condition>,
-) -> ParseResult<()> {
-    let mut new_conditions = condition_section
-        .into_inner()
-        .filter(|condition| matches!(condition.as_rule(), Rule::condition))
-        .map(parse_condition)
-        .collect::<ParseResult<Vec<_>>>()?;
+) -> ParseResults<()> {
+    let mut new_conditions = collect_all(
+        condition_section
+            .into_inner()
+            .filter(|condition| matches!(condition.as_rule(), Rule::condition))
+            .map(parse_condition),
+    )?;
 
     conditions.append(&mut new_conditions);
     Ok(())
 }
 
 fn parse_condition(condition: Pair<Rule>) -> ParseResult<Condition> {
-    let condition = condition
-        .into_inner()
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?;
+    let condition = first_inner(condition)?;
     let c = match condition.as_rule() {
         Rule::number_condition => parse_number_condition(condition)?,
         Rule::string_condition => parse_string_condition(condition)?,
+        Rule::bool_condition => parse_bool_condition(condition)?,
         Rule::legacy_condition => parse_legacy_condition(condition)?,
         _ => unreachable!(),
     };
@@ -159,12 +423,16 @@ fn parse_condition(condition: Pair<Rule>) -> ParseResult<Condition> {
 }
 
 fn parse_number_condition(number_condition: Pair<Rule>) -> ParseResult<Condition> {
+    let span = number_condition.as_span();
     let mut inner = number_condition.into_inner();
-    let name = inner.next().ok_or(ParseError::UnexpectedEnd)?.as_str();
-    let operation = inner.next().ok_or(ParseError::UnexpectedEnd)?;
-    let number_string = inner.next().ok_or(ParseError::UnexpectedEnd)?.as_str();
+    let name = next(&mut inner, span)?.as_str();
+    let operation = next(&mut inner, span)?;
+    let number = next(&mut inner, span)?;
 
-    let value = number_string.parse().map_err(ParseError::NumParse)?;
+    let value = number
+        .as_str()
+        .parse()
+        .map_err(|e| ParseError::new(ParseErrorKind::NumParse(e), &number))?;
     let operation = match operation.as_rule() {
         Rule::compare_eq => NumberCondition::Eq(value),
         Rule::compare_lt => NumberCondition::Lt(value),
@@ -180,28 +448,19 @@ fn parse_number_condition(number_condition: Pair<Rule>) -> ParseResult<Condition
 }
 
 fn parse_string_condition(string_condition: Pair<Rule>) -> ParseResult<Condition> {
+    let span = string_condition.as_span();
     let mut inner = string_condition.into_inner();
-    let name = inner
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?
-        .into_inner()
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?
-        .as_rule();
-    let eq = inner
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?
-        .into_inner()
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?
-        .as_rule();
-    let value = inner.next().ok_or(ParseError::UnexpectedEnd)?.as_str();
+    let name = first_inner(next(&mut inner, span)?)?.as_rule();
+    let eq = first_inner(next(&mut inner, span)?)?.as_rule();
+    let value_pair = next(&mut inner, span)?;
+    let value = value_pair.as_str();
 
     let condition_type = match eq {
         Rule::compare_eq => ConditionTypeString::Literal(value.to_owned()),
-        Rule::compare_match => {
-            ConditionTypeString::Regex(Regex::new(value).map_err(ParseError::Regex)?)
-        }
+        Rule::compare_match => ConditionTypeString::Regex(
+            Regex::new(value)
+                .map_err(|e| ParseError::new(ParseErrorKind::Regex(e), &value_pair))?,
+        ),
         _ => unreachable!(),
     };
 
@@ -209,23 +468,39 @@ fn parse_string_condition(string_condition: Pair<Rule>) -> ParseResult<Condition
         Rule::summary => Ok(Condition::Summary(condition_type)),
         Rule::body => Ok(Condition::Body(condition_type)),
         Rule::group => Ok(Condition::Group(condition_type)),
+        Rule::category => Ok(Condition::Category(condition_type)),
+        Rule::desktop_entry => Ok(Condition::DesktopEntry(condition_type)),
+        Rule::app_id => Ok(Condition::AppId(condition_type)),
+        _ => unreachable!(),
+    }
+}
+
+fn parse_bool_condition(bool_condition: Pair<Rule>) -> ParseResult<Condition> {
+    let span = bool_condition.as_span();
+    let mut inner = bool_condition.into_inner();
+    let name = first_inner(next(&mut inner, span)?)?.as_rule();
+    let mut inner = inner.skip(1);
+    let value = inner
+        .next()
+        .ok_or_else(|| ParseError::at_end(ParseErrorKind::UnexpectedEnd, span))?
+        .as_str()
+        == "true";
+
+    match name {
+        Rule::transient => Ok(Condition::Transient(value)),
+        Rule::resident => Ok(Condition::Resident(value)),
         _ => unreachable!(),
     }
 }
 
 fn parse_legacy_condition(legacy_condition: Pair<Rule>) -> ParseResult<Condition> {
+    let span = legacy_condition.as_span();
     let mut inner = legacy_condition.into_inner();
-    let name = inner
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?
-        .into_inner()
-        .next()
-        .ok_or(ParseError::UnexpectedEnd)?
-        .as_rule();
+    let name = first_inner(next(&mut inner, span)?)?.as_rule();
     let mut inner = inner.skip(1);
     let value = inner
         .next()
-        .ok_or(ParseError::UnexpectedEnd)?
+        .ok_or_else(|| ParseError::at_end(ParseErrorKind::UnexpectedEnd, span))?
         .as_str()
         .to_owned();
 
@@ -237,61 +512,183 @@ fn parse_legacy_condition(legacy_condition: Pair<Rule>) -> ParseResult<Condition
     }
 }
 
-fn parse_style_section(style_section: Pair<Rule>) -> ParseResult<Vec<Style>> {
-    style_section.into_inner().map(parse_style).collect()
+fn parse_style_section(style_section: Pair<Rule>) -> ParseResults<Vec<Style>> {
+    collect_all(style_section.into_inner().map(parse_style))
 }
 
 fn parse_style(style: Pair<Rule>) -> ParseResult<Style> {
-    let style = style.into_inner().next().ok_or(ParseError::UnexpectedEnd)?;
+    let style = first_inner(style)?;
     match style.as_rule() {
         Rule::background_style => {
-            let color = style
-                .into_inner()
-                .next()
-                .ok_or(ParseError::UnexpectedEnd)?
-                .as_str()
-                .to_owned();
+            let color = first_inner(style)?.as_str().to_owned();
             Ok(Style::Background(color))
         }
         Rule::text_style => {
-            let color = style
-                .into_inner()
-                .next()
-                .ok_or(ParseError::UnexpectedEnd)?
-                .as_str()
-                .to_owned();
+            let color = first_inner(style)?.as_str().to_owned();
             Ok(Style::Text(color))
         }
         _ => unreachable!(),
     }
 }
 
+fn parse_schedule(schedule: Pair<Rule>) -> ParseResults<Vec<ScheduleEntry>> {
+    collect_all(schedule.into_inner().map(parse_schedule_entry))
+}
+
+fn parse_schedule_entry(entry: Pair<Rule>) -> ParseResult<ScheduleEntry> {
+    let span = entry.as_span();
+    let mut days = ScheduleEntry::ALL_DAYS;
+    let mut times = Vec::with_capacity(2);
+    let mut urgency = MinimalUrgency::None;
+    for part in entry.into_inner() {
+        match part.as_rule() {
+            Rule::weekdays => {
+                days = part
+                    .into_inner()
+                    .map(parse_weekday_range)
+                    .fold(0, |days, range| days | range)
+            }
+            Rule::time_range => {
+                times = part
+                    .into_inner()
+                    .map(|t| {
+                        NaiveTime::parse_from_str(t.as_str(), "%H:%M")
+                            .map_err(|e| ParseError::new(ParseErrorKind::Time(e), &t))
+                    })
+                    .collect::<Result<_, _>>()?
+            }
+            Rule::schedule_urgency => {
+                urgency = MinimalUrgency::from_str(part.as_str()).unwrap_or_else(|_| unreachable!())
+            }
+            _ => unreachable!(),
+        }
+    }
+    let [start, end] = times[..] else {
+        return Err(ParseError::at_end(ParseErrorKind::UnexpectedEnd, span));
+    };
+    Ok(ScheduleEntry::new(days, start, end, urgency))
+}
+
+fn parse_weekday_range(range: Pair<Rule>) -> u8 {
+    let mut days = range
+        .into_inner()
+        .filter_map(|day| Weekday::from_str(day.as_str()).ok());
+    match (days.next(), days.next()) {
+        (Some(first), Some(last)) => ScheduleEntry::day_range(first, last),
+        (Some(day), None) => ScheduleEntry::day_range(day, day),
+        _ => 0,
+    }
+}
+
 pub type ParseResult<T> = Result<T, ParseError>;
+/// Result of parsing a section which reports all errors in it
+pub type ParseResults<T> = Result<T, Vec<ParseError>>;
+
+/// Error at a position in the config file. Line and column start at 1.
+#[derive(Debug)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
 
 #[derive(Debug)]
-pub enum ParseError {
+pub enum ParseErrorKind {
     PestError(Box<pest::error::Error<Rule>>),
     UnexpectedEnd,
     NumParse(std::num::ParseIntError),
     EmojiMode(emoji::EmojiModeError),
     Regex(regex::Error),
+    Time(chrono::ParseError),
+    UnterminatedQuote,
+    Template,
+    UnknownIcon(String),
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, pair: &Pair<Rule>) -> Self {
+        let (line, column) = pair.as_span().start_pos().line_col();
+        Self { kind, line, column }
+    }
+
+    /// Error `column` characters after the start of `pair`
+    fn at_offset(kind: ParseErrorKind, pair: &Pair<Rule>, column: usize) -> Self {
+        let (line, start) = pair.as_span().start_pos().line_col();
+        Self {
+            kind,
+            line,
+            column: start + column,
+        }
+    }
+
+    fn at_end(kind: ParseErrorKind, span: Span) -> Self {
+        let (line, column) = span.end_pos().line_col();
+        Self { kind, line, column }
+    }
+}
+
+impl From<pest::error::Error<Rule>> for ParseError {
+    fn from(e: pest::error::Error<Rule>) -> Self {
+        let (line, column) = match e.line_col {
+            LineColLocation::Pos(pos) | LineColLocation::Span(pos, _) => pos,
+        };
+        Self {
+            kind: ParseErrorKind::PestError(Box::new(e)),
+            line,
+            column,
+        }
+    }
 }
 
 impl Display for ParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.kind)
+    }
+}
+
+impl Error for ParseError {}
+
+impl Display for ParseErrorKind {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let e: &dyn Error = match self {
-            Self::PestError(e) => e,
+            // The position is already part of ParseError
+            Self::PestError(e) => return write!(f, "{}", e.variant.message()),
             Self::UnexpectedEnd => return write!(f, "Unexpected end of config file"),
+            Self::UnterminatedQuote => return write!(f, "Quote or template is not closed"),
+            Self::Template => return write!(f, "Invalid template"),
+            Self::UnknownIcon(name) => return write!(f, "Unknown icon {}", name),
             Self::NumParse(e) => e,
             Self::EmojiMode(e) => e,
             Self::Regex(e) => e,
+            Self::Time(e) => e,
         };
 
         Display::fmt(e, f)
     }
 }
 
-impl Error for ParseError {}
+/// Config containing errors
+#[derive(Debug)]
+pub struct InvalidConfig {
+    /// All rules and schedule entries without errors
+    pub config: Config,
+    /// Errors sorted by their position
+    pub errors: Vec<ParseError>,
+}
+
+impl Display for InvalidConfig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for (i, e) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{e}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for InvalidConfig {}
 
 #[cfg(test)]
 mod tests {
@@ -332,6 +729,71 @@ mod tests {
         assert_eq!(condition, Condition::AppName("test".to_owned()));
     }
 
+    #[test]
+    fn parse_string_condition_category() {
+        let condition = ConfigParser::parse(Rule::string_condition, "category match ^email\\.")
+            .unwrap()
+            .next()
+            .unwrap();
+        let condition = parse_string_condition(condition).unwrap();
+        assert_eq!(
+            condition,
+            Condition::Category(ConditionTypeString::Regex(Regex::new("^email\\.").unwrap()))
+        );
+    }
+
+    #[test]
+    fn parse_string_condition_desktop_entry() {
+        let condition = ConfigParser::parse(Rule::string_condition, "desktop_entry = firefox")
+            .unwrap()
+            .next()
+            .unwrap();
+        let condition = parse_string_condition(condition).unwrap();
+        assert_eq!(
+            condition,
+            Condition::DesktopEntry(ConditionTypeString::Literal("firefox".to_owned()))
+        );
+    }
+
+    #[test]
+    fn parse_string_condition_app_id() {
+        let condition = ConfigParser::parse(Rule::string_condition, "app_id match ^google-")
+            .unwrap()
+            .next()
+            .unwrap();
+        let condition = parse_string_condition(condition).unwrap();
+        assert_eq!(
+            condition,
+            Condition::AppId(ConditionTypeString::Regex(Regex::new("^google-").unwrap()))
+        );
+    }
+
+    #[test]
+    fn parse_bool_condition_transient() {
+        let condition = ConfigParser::parse(Rule::bool_condition, "transient = true")
+            .unwrap()
+            .next()
+            .unwrap();
+        let condition = parse_bool_condition(condition).unwrap();
+        assert_eq!(condition, Condition::Transient(true));
+    }
+
+    #[test]
+    fn parse_bool_condition_resident() {
+        let condition = ConfigParser::parse(Rule::bool_condition, "resident = false")
+            .unwrap()
+            .next()
+            .unwrap();
+        let condition = parse_bool_condition(condition).unwrap();
+        assert_eq!(condition, Condition::Resident(false));
+    }
+
+    #[test]
+    fn parse_bool_condition_invalid_value() {
+        let condition = ConfigParser::parse(Rule::bool_condition, "resident = yes");
+        assert!(condition.is_err());
+    }
+
     #[test]
     fn parse_number_condition_expire_timeout() {
         let condition = ConfigParser::parse(Rule::number_condition, "expire_timeout = 42")
@@ -455,6 +917,93 @@ mod tests {
         assert_eq!(actions[2], Action::Ignore);
     }
 
+    #[test]
+    fn split_args_quotes_and_templates() {
+        assert_eq!(
+            split_args(r#"notify-send "a b" 'c "d"' e\ f {{ summary | upper }}x"#),
+            Some(vec![
+                (0, "notify-send".to_owned()),
+                (12, "a b".to_owned()),
+                (18, r#"c "d""#.to_owned()),
+                (26, "e f".to_owned()),
+                (31, "{{ summary | upper }}x".to_owned()),
+            ])
+        );
+        assert_eq!(split_args(r#"echo "a"#), None);
+        assert_eq!(split_args("echo {{ a"), None);
+    }
+
+    #[test]
+    fn parse_exec_action() {
+        let action = ConfigParser::parse(Rule::action, "exec paplay 'my sound.ogg'")
+            .unwrap()
+            .next()
+            .unwrap();
+        let Action::Exec(args) = parse_action(action).unwrap() else {
+            panic!("Expected exec action");
+        };
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn parse_exec_action_invalid_template() {
+        let config = r#"rule
+    action
+        exec echo {{ }}}}
+    end
+end"#;
+        let config = parse_config(&mut config.as_bytes()).unwrap_err();
+        assert_eq!(config.errors.len(), 1);
+        assert!(matches!(config.errors[0].kind, ParseErrorKind::Template));
+        assert_eq!((config.errors[0].line, config.errors[0].column), (3, 19));
+    }
+
+    #[test]
+    fn parse_on_click() {
+        let section = ConfigParser::parse(
+            Rule::on_click_section,
+            "on_click\n1 action open\nctrl+shift+1 dismiss\n2 copy body\n3 action_bar\n4 expand\n5 exec notify-send scrolled\nend",
+        )
+        .unwrap()
+        .next()
+        .unwrap();
+        let bindings = parse_on_click_section(section).unwrap();
+        let behaviours = bindings.iter().map(|b| &b.behaviour).collect::<Vec<_>>();
+        assert_eq!(
+            behaviours[..5],
+            [
+                &ClickBehaviour::InvokeAction("open".to_owned()),
+                &ClickBehaviour::Dismiss,
+                &ClickBehaviour::Copy(CopySource {
+                    field: CopyField::Body,
+                    regex: None
+                }),
+                &ClickBehaviour::ActionBar,
+                &ClickBehaviour::Expand,
+            ]
+        );
+        assert!(matches!(&bindings[5].behaviour, ClickBehaviour::Exec(args) if args.len() == 2));
+        assert_eq!(bindings[1].button, 1);
+        assert_eq!(bindings[1].modifiers, ["control", "shift"]);
+        assert_eq!(bindings[5].button, 5);
+    }
+
+    #[test]
+    fn parse_copy_regex() {
+        let binding = ConfigParser::parse(Rule::click_binding, "1 copy summary match code (\\d+)")
+            .unwrap()
+            .next()
+            .unwrap();
+        let binding = parse_click_binding(binding).unwrap();
+        assert_eq!(
+            binding.behaviour,
+            ClickBehaviour::Copy(CopySource {
+                field: CopyField::Summary,
+                regex: Some(Regex::new("code (\\d+)").unwrap())
+            })
+        );
+    }
+
     #[test]
     fn parse_simple_config() {
         let config = r#"rule
@@ -470,21 +1019,200 @@ mod tests {
         end"#;
         let config = parse_config(&mut config.as_bytes());
         assert_eq!(
-            config.unwrap(),
+            config.unwrap().definitions,
             vec![Definition {
                 conditions: vec![Condition::AppName("Thunderbird".to_owned())],
                 actions: vec![Action::Set(SetProperty::ExpireTimeout(-1))],
                 style: vec![Style::Background("#ff00ff".to_owned())],
-                sub_definition: vec![]
+                sub_definition: vec![],
+                on_click: vec![]
             }]
         )
     }
 
+    #[test]
+    fn parse_schedule_config() {
+        let config = r#"schedule
+    22:00-07:00 critical
+    mon-wed,fri 09:30-09:45 off
+end
+rule
+    action
+        ignore
+    end
+end"#;
+        let config = parse_config(&mut config.as_bytes()).unwrap();
+        assert_eq!(config.definitions.len(), 1);
+        assert_eq!(
+            config.schedule,
+            vec![
+                ScheduleEntry::new(
+                    ScheduleEntry::ALL_DAYS,
+                    NaiveTime::from_hms_opt(22, 0, 0).unwrap(),
+                    NaiveTime::from_hms_opt(7, 0, 0).unwrap(),
+                    MinimalUrgency::Critical
+                ),
+                ScheduleEntry::new(
+                    0b001_0111,
+                    NaiveTime::from_hms_opt(9, 30, 0).unwrap(),
+                    NaiveTime::from_hms_opt(9, 45, 0).unwrap(),
+                    MinimalUrgency::None
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_schedule_overlapping_days() {
+        let config = "schedule\n    mon-wed,tue 09:00-10:00 off\n    mon-sun,mon-sun,mon-sun 12:00-13:00 off\nend";
+        let config = parse_config(&mut config.as_bytes()).unwrap();
+        let time = |h| NaiveTime::from_hms_opt(h, 0, 0).unwrap();
+        assert_eq!(
+            config.schedule,
+            vec![
+                ScheduleEntry::new(0b000_0111, time(9), time(10), MinimalUrgency::None),
+                ScheduleEntry::new(
+                    ScheduleEntry::ALL_DAYS,
+                    time(12),
+                    time(13),
+                    MinimalUrgency::None
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_schedule_invalid_time() {
+        let config = "schedule\n    25:00-07:00 critical\nend";
+        let config = parse_config(&mut config.as_bytes()).unwrap_err();
+        assert!(matches!(config.errors[0].kind, ParseErrorKind::Time(_)));
+        assert_eq!((config.errors[0].line, config.errors[0].column), (2, 5));
+    }
+
+    #[test]
+    fn parse_config_collects_errors() {
+        let config = r#"rule
+    condition
+        body match (
+    end
+    action
+        set expire_timeout 99999999999
+    end
+end
+rule
+    action
+        set emoji_mode nothing
+    end
+end
+rule
+    action
+        ignore
+    end
+end"#;
+        let config = parse_config(&mut config.as_bytes()).unwrap_err();
+        let positions = config
+            .errors
+            .iter()
+            .map(|e| (e.line, e.column))
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![(3, 20), (6, 28), (11, 24)]);
+        assert!(matches!(config.errors[0].kind, ParseErrorKind::Regex(_)));
+        assert!(matches!(config.errors[1].kind, ParseErrorKind::NumParse(_)));
+        assert!(matches!(
+            config.errors[2].kind,
+            ParseErrorKind::EmojiMode(_)
+        ));
+        assert_eq!(
+            config.config.definitions,
+            vec![Definition {
+                actions: vec![Action::Ignore],
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_config_skips_syntax_errors() {
+        let config = r#"rule
+    condition
+        app_name Thunderbird
+    end
+end
+rule
+    actio
+        ignore
+    end
+end
+schedule
+    22:00-07:00 critical
+end
+rule
+    action
+        stop
+    end
+"#;
+        let config = parse_config(&mut config.as_bytes()).unwrap_err();
+        let lines = config.errors.iter().map(|e| e.line).collect::<Vec<_>>();
+        assert_eq!(lines, vec![3, 7, 18]);
+        assert!(config.config.definitions.is_empty());
+        assert_eq!(config.config.schedule.len(), 1);
+    }
+
+    #[test]
+    fn parse_set_app_icon() {
+        let config = r#"rule
+    action
+        set app_icon U+F392
+    end
+end
+rule
+    action
+        set app_icon not_an_icon
+    end
+end"#;
+        let config = parse_config(&mut config.as_bytes()).unwrap_err();
+        assert_eq!(config.errors.len(), 1);
+        assert_eq!((config.errors[0].line, config.errors[0].column), (8, 22));
+        assert!(matches!(
+            &config.errors[0].kind,
+            ParseErrorKind::UnknownIcon(name) if name == "not_an_icon"
+        ));
+        assert_eq!(
+            config.config.definitions,
+            vec![Definition {
+                actions: vec![Action::Set(SetProperty::Icon('\u{F392}'))],
+                ..Default::default()
+            }]
+        );
+    }
+
+    #[test]
+    fn parse_config_missing_end() {
+        let config = r#"rule
+    action
+        ignore
+    end
+rule
+    action
+        stop
+    end
+end"#;
+        let config = parse_config(&mut config.as_bytes()).unwrap_err();
+        assert_eq!(config.errors.len(), 1);
+        assert_eq!(
+            config.config.definitions,
+            vec![Definition {
+                actions: vec![Action::Stop],
+                ..Default::default()
+            }]
+        );
+    }
+
     #[test]
     fn parse_empty_config() {
         let config = "   \n ";
         let config = parse_config(&mut config.as_bytes()).unwrap();
-        assert_eq!(config, vec![])
+        assert_eq!(config, Config::default())
     }
 
     #[test]
@@ -506,7 +1234,7 @@ rule
 end"#;
         let config = parse_config(&mut config.as_bytes()).unwrap();
         assert_eq!(
-            config,
+            config.definitions,
             vec![
                 Definition {
                     conditions: vec![Condition::AppName("Thunderbird".to_owned())],
@@ -582,6 +1310,21 @@ mod pest_tests {
         rule_test!(urgency, "urgency" "=" "test urgency");
     }
 
+    #[test]
+    fn rule_category() {
+        rule_test!(category, "category" "match" "^im\\.");
+    }
+
+    #[test]
+    fn rule_desktop_entry() {
+        rule_test!(desktop_entry, "desktop_entry" "=" "org.mozilla.firefox");
+    }
+
+    #[test]
+    fn rule_transient() {
+        rule_test!(transient, "transient" "=" "true");
+    }
+
     #[test]
     fn rule_expire_timeout() {
         rule_test!(expire_timeout, "expire_timeout" "=" "test expire_timeouturgency");
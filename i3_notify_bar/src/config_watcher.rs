@@ -0,0 +1,65 @@
+use std::{
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Polls the modification time of the config file.
+pub struct ConfigWatcher {
+    path: PathBuf,
+    last_modified: Option<SystemTime>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: PathBuf) -> Self {
+        let last_modified = modified(&path);
+        Self {
+            path,
+            last_modified,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if the file was modified, created or removed since the last call.
+    pub fn changed(&mut self) -> bool {
+        let modified = modified(&self.path);
+        if modified == self.last_modified {
+            return false;
+        }
+        self.last_modified = modified;
+        true
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use super::ConfigWatcher;
+
+    #[test]
+    fn detects_changes() {
+        let path = std::env::temp_dir().join(format!(
+            "i3_notify_bar_watch_test_{}.conf",
+            std::process::id()
+        ));
+        std::fs::write(&path, "").unwrap();
+        let mut watcher = ConfigWatcher::new(path.clone());
+        assert!(!watcher.changed());
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(10))
+            .unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(watcher.changed());
+    }
+}
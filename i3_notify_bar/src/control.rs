@@ -0,0 +1,441 @@
+use std::{
+    io::{BufRead, BufReader, Write},
+    os::unix::{
+        fs::FileTypeExt,
+        net::{UnixListener, UnixStream},
+    },
+    path::Path,
+    sync::mpsc::Sender,
+    time::Duration,
+};
+
+use log::{debug, error, warn};
+use notify_server::{notification::Urgency, CloseReason};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    notification_bar::{
+        CloseAllNotifications as _, CloseNotification as _, InvokeAction as _, MinimalUrgency,
+        NotificationData, NotificationManagerCommands,
+    },
+    SystemCommand,
+};
+
+/// Time to wait for the notification manager or the bar to answer
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Command sent to the control socket. Every command is a single line of JSON.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum ControlCommand {
+    /// Closes the notification with the given id or the newest notification if no id is given
+    Close {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<u32>,
+    },
+    CloseAll,
+    InvokeAction {
+        id: u32,
+        action: String,
+    },
+    SetMinUrgency {
+        urgency: MinimalUrgency,
+    },
+    ToggleDnd,
+    List,
+    /// Click on a block reported by bars like polybar
+    Click {
+        instance: usize,
+        button: usize,
+    },
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ControlResponse {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notifications: Option<Vec<NotificationInfo>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NotificationInfo {
+    pub id: u32,
+    pub app_name: String,
+    pub summary: String,
+    pub body: String,
+    pub urgency: String,
+    pub text: String,
+    pub group: Option<String>,
+    pub actions: Vec<String>,
+    pub time: i64,
+}
+
+impl From<&NotificationData> for NotificationInfo {
+    fn from(nd: &NotificationData) -> Self {
+        Self {
+            id: nd.id.into(),
+            app_name: nd.app_name.clone(),
+            summary: nd.summary.clone(),
+            body: nd.body.clone(),
+            urgency: match nd.urgency {
+                Urgency::Low => "low",
+                Urgency::Normal => "normal",
+                Urgency::Critical => "critical",
+            }
+            .to_owned(),
+            text: nd.plain_text(),
+            group: nd.group.clone(),
+            actions: nd.actions.iter().map(|a| a.key.clone()).collect(),
+            time: nd.time,
+        }
+    }
+}
+
+impl ControlResponse {
+    fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            notifications: None,
+        }
+    }
+}
+
+/// Listens on `path` for control commands and forwards them to the notification manager.
+pub fn start_server(
+    path: &Path,
+    commands: NotificationManagerCommands,
+    system_commands: Sender<SystemCommand>,
+) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // A socket file left over from a previous run would make bind fail. Anything else at the path,
+    // including the socket of a running instance, is left alone.
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if !metadata.file_type().is_socket() => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                format!("{} exists and is not a socket", path.display()),
+            ));
+        }
+        Ok(_) if UnixStream::connect(path).is_ok() => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AddrInUse,
+                format!("{} is used by another instance", path.display()),
+            ));
+        }
+        Ok(_) => std::fs::remove_file(path)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    let listener = UnixListener::bind(path)?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(s) => s,
+                Err(e) => {
+                    error!("Control socket connection failed: {e}");
+                    continue;
+                }
+            };
+            let commands = commands.clone();
+            let system_commands = system_commands.clone();
+            std::thread::spawn(move || handle_client(stream, &commands, &system_commands));
+        }
+    });
+    Ok(())
+}
+
+fn handle_client(
+    stream: UnixStream,
+    commands: &NotificationManagerCommands,
+    system_commands: &Sender<SystemCommand>,
+) {
+    let mut writer = match stream.try_clone() {
+        Ok(w) => w,
+        Err(e) => {
+            error!("Could not clone control socket stream: {e}");
+            return;
+        }
+    };
+    for line in BufReader::new(stream).lines().map_while(Result::ok) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        debug!("Control command {line}");
+        let response = match serde_json::from_str(&line) {
+            Ok(command) => execute(command, commands, system_commands),
+            Err(e) => {
+                warn!("Invalid control command {line}: {e}");
+                ControlResponse::error(format!("Invalid command: {e}"))
+            }
+        };
+        let Ok(mut response) = serde_json::to_string(&response) else {
+            return;
+        };
+        response.push('\n');
+        if writer.write_all(response.as_bytes()).is_err() {
+            return;
+        }
+    }
+}
+
+fn execute(
+    command: ControlCommand,
+    commands: &NotificationManagerCommands,
+    system_commands: &Sender<SystemCommand>,
+) -> ControlResponse {
+    let response = match command {
+        ControlCommand::Close { id: Some(id) } => {
+            commands.notification_closed(id.into(), CloseReason::Dismissed);
+            ControlResponse::ok()
+        }
+        ControlCommand::Close { id: None } => {
+            match list(commands, system_commands).map(|n| n.last().map(|n| n.id)) {
+                Ok(Some(id)) => {
+                    commands.notification_closed(id.into(), CloseReason::Dismissed);
+                    ControlResponse::ok()
+                }
+                Ok(None) => ControlResponse::error("No notifications"),
+                Err(e) => e,
+            }
+        }
+        ControlCommand::CloseAll => {
+            commands.close_all_notifications(CloseReason::Dismissed);
+            ControlResponse::ok()
+        }
+        ControlCommand::InvokeAction { id, action } => {
+            commands.action_invoked(id.into(), action);
+            ControlResponse::ok()
+        }
+        ControlCommand::SetMinUrgency { urgency } => {
+            commands.set_minimal_urgency(urgency);
+            ControlResponse::ok()
+        }
+        ControlCommand::ToggleDnd => {
+            commands.toggle_do_not_disturb();
+            ControlResponse::ok()
+        }
+        ControlCommand::Click { instance, button } => {
+            let (handled, rx) = std::sync::mpsc::channel();
+            system_commands
+                .send(SystemCommand::Click {
+                    instance,
+                    button,
+                    handled,
+                })
+                .ok();
+            match rx.recv_timeout(REPLY_TIMEOUT) {
+                Ok(true) => ControlResponse::ok(),
+                Ok(false) => ControlResponse::error("The output does not support clicks"),
+                Err(_) => ControlResponse::error("Bar did not respond"),
+            }
+        }
+        ControlCommand::List => match list(commands, system_commands) {
+            Ok(notifications) => ControlResponse {
+                notifications: Some(notifications),
+                ..ControlResponse::ok()
+            },
+            Err(e) => e,
+        },
+    };
+    // Handle the command right away instead of waiting for the next refresh
+    system_commands.send(SystemCommand::ForceUpdate).ok();
+    response
+}
+
+fn list(
+    commands: &NotificationManagerCommands,
+    system_commands: &Sender<SystemCommand>,
+) -> Result<Vec<NotificationInfo>, ControlResponse> {
+    let rx = commands.list_notifications();
+    system_commands.send(SystemCommand::ForceUpdate).ok();
+    rx.recv_timeout(REPLY_TIMEOUT)
+        .map_err(|_| ControlResponse::error("Notification manager did not respond"))
+}
+
+/// Sends a single command to a running instance and returns its response.
+pub fn send(path: &Path, command: &ControlCommand) -> std::io::Result<ControlResponse> {
+    let mut stream = UnixStream::connect(path)?;
+    let mut request = serde_json::to_string(command)?;
+    request.push('\n');
+    stream.write_all(request.as_bytes())?;
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response)?;
+    Ok(serde_json::from_str(&response)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use super::{ControlCommand, ControlResponse};
+    use crate::{
+        history::NotificationHistory,
+        notification_bar::{MinimalUrgency, NotificationManager, SuppressMode},
+        rule::RuleExcutor,
+        SystemCommand,
+    };
+
+    #[test]
+    fn parse_commands() {
+        let cases = [
+            (
+                r#"{"command":"close","id":3}"#,
+                ControlCommand::Close { id: Some(3) },
+            ),
+            (r#"{"command":"close"}"#, ControlCommand::Close { id: None }),
+            (r#"{"command":"close_all"}"#, ControlCommand::CloseAll),
+            (
+                r#"{"command":"invoke_action","id":2,"action":"default"}"#,
+                ControlCommand::InvokeAction {
+                    id: 2,
+                    action: "default".into(),
+                },
+            ),
+            (
+                r#"{"command":"set_min_urgency","urgency":"off"}"#,
+                ControlCommand::SetMinUrgency {
+                    urgency: MinimalUrgency::None,
+                },
+            ),
+            (r#"{"command":"toggle_dnd"}"#, ControlCommand::ToggleDnd),
+            (r#"{"command":"list"}"#, ControlCommand::List),
+            (
+                r#"{"command":"click","instance":4,"button":1}"#,
+                ControlCommand::Click {
+                    instance: 4,
+                    button: 1,
+                },
+            ),
+        ];
+        for (json, expected) in cases {
+            assert_eq!(
+                serde_json::from_str::<ControlCommand>(json).ok(),
+                Some(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn serialize_response() {
+        assert_eq!(
+            serde_json::to_string(&ControlResponse::ok()).ok(),
+            Some(r#"{"ok":true}"#.to_owned())
+        );
+        assert_eq!(
+            serde_json::to_string(&ControlResponse::error("nope")).ok(),
+            Some(r#"{"ok":false,"error":"nope"}"#.to_owned())
+        );
+    }
+
+    #[async_std::test]
+    async fn socket_round_trip() {
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().returning(|| None);
+        let minimal_urgency = Arc::new(RwLock::new(MinimalUrgency::Normal));
+        let mut nm = NotificationManager::new(
+            emoji::EmojiMode::Ignore,
+            Arc::clone(&minimal_urgency),
+            Arc::new(RwLock::new(SuppressMode::Drop)),
+            Arc::new(RwLock::new(NotificationHistory::new(0))),
+            notify_src,
+            RuleExcutor::new(vec![]),
+        );
+        let path =
+            std::env::temp_dir().join(format!("i3_notify_bar_test_{}.sock", std::process::id()));
+        let (system_tx, _system_rx) = std::sync::mpsc::channel();
+        super::start_server(&path, nm.linked_commands(), system_tx).unwrap();
+
+        let response = super::send(
+            &path,
+            &ControlCommand::SetMinUrgency {
+                urgency: MinimalUrgency::Critical,
+            },
+        )
+        .unwrap();
+        assert!(response.ok);
+        nm.update(0.0).await;
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::Critical);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn start_server_only_replaces_stale_sockets() {
+        let dir =
+            std::env::temp_dir().join(format!("i3_notify_bar_control_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let nm = NotificationManager::new(
+            emoji::EmojiMode::Ignore,
+            Arc::new(RwLock::new(MinimalUrgency::Normal)),
+            Arc::new(RwLock::new(SuppressMode::Drop)),
+            Arc::new(RwLock::new(NotificationHistory::new(0))),
+            notify_server::MockNotificationSource::default(),
+            RuleExcutor::new(vec![]),
+        );
+        let start = |path: &std::path::Path| {
+            let (system_tx, _) = std::sync::mpsc::channel();
+            super::start_server(path, nm.linked_commands(), system_tx)
+        };
+
+        let file = dir.join("file");
+        std::fs::write(&file, "keep").unwrap();
+        assert!(start(&file).is_err());
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep");
+
+        let stale = dir.join("stale.sock");
+        drop(std::os::unix::net::UnixListener::bind(&stale).unwrap());
+        start(&stale).unwrap();
+        // The socket is in use now
+        assert_eq!(
+            start(&stale).unwrap_err().kind(),
+            std::io::ErrorKind::AddrInUse
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn click_without_click_support() {
+        let nm = NotificationManager::new(
+            emoji::EmojiMode::Ignore,
+            Arc::new(RwLock::new(MinimalUrgency::Normal)),
+            Arc::new(RwLock::new(SuppressMode::Drop)),
+            Arc::new(RwLock::new(NotificationHistory::new(0))),
+            notify_server::MockNotificationSource::default(),
+            RuleExcutor::new(vec![]),
+        );
+        let (system_tx, system_rx) = std::sync::mpsc::channel();
+        // Bar whose output drops clicks
+        std::thread::spawn(move || {
+            for command in system_rx {
+                if let SystemCommand::Click { handled, .. } = command {
+                    handled.send(false).unwrap();
+                }
+            }
+        });
+        let response = super::execute(
+            ControlCommand::Click {
+                instance: 1,
+                button: 1,
+            },
+            &nm.linked_commands(),
+            &system_tx,
+        );
+        assert!(!response.ok);
+        assert_eq!(
+            response.error.as_deref(),
+            Some("The output does not support clicks")
+        );
+    }
+}
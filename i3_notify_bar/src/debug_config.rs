@@ -1,15 +1,15 @@
 use std::fmt::Display;
 
-use notify_server::notification::{NotificationBuilder, Urgency};
+use notify_server::notification::NotificationBuilder;
 
 use crate::{
     args::DebugConfig,
-    notification_bar::{execute_rules, NotificationData, NotificationTemplateData},
-    rule::Definition,
+    notification_bar::{NotificationData, NotificationTemplateData},
+    rule::{Action, Conditions, Definition, RuleExcutor, Trace},
     EmojiMode,
 };
 
-pub fn debug_config(config: &[Definition], emoji_mode: EmojiMode, debug_config: DebugConfig) {
+pub fn debug_config(config: Vec<Definition>, emoji_mode: EmojiMode, debug_config: DebugConfig) {
     let DebugConfig {
         app_icon,
         app_name,
@@ -17,7 +17,8 @@ pub fn debug_config(config: &[Definition], emoji_mode: EmojiMode, debug_config:
         expire_timeout,
         id,
         summary,
-        urgency: _,
+        urgency,
+        hints,
     } = debug_config;
 
     let notification = NotificationBuilder::default()
@@ -27,74 +28,159 @@ pub fn debug_config(config: &[Definition], emoji_mode: EmojiMode, debug_config:
         .with_summary(summary)
         .with_body(body)
         .with_actions(Vec::default())
-        .with_urgency(Urgency::default())
+        .with_urgency(urgency)
         .with_expire_timeout(expire_timeout)
+        .with_hints(hints)
         .build();
 
     let mut notification_data = NotificationData::new(&notification, emoji_mode);
-
     let mut notification_template_data = NotificationTemplateData::from(&notification);
 
-    let matched_rules = execute_rules(
-        config,
+    let mut trace = TraceTree::default();
+    RuleExcutor::new(config).trace(
         &notification,
         &mut notification_template_data,
         &mut notification_data,
+        &mut trace,
     );
-    drop(notification_template_data);
-
-    println!("##### Matched Rules #####");
-    println!("{matched_rules}");
-
-    println!(
-        "##### Notification #####\nid: {}\nexpire_timeout: {}\nicon: {}\ntext: {}\nstyle: {:#?}\nemoji_mode: {:#?}",
-        notification_data.id,
-        notification_data.expire_timeout,
-        notification_data.icon,
-        notification_data.text,
-        notification_data.style,
-        notification_data.emoji_mode
-    );
+
+    println!("##### Rules #####");
+    print!("{trace}");
+    println!("##### Notification #####");
+    println!("{notification_data:#?}");
 }
 
-pub struct MatchedDefinitionTree {
-    id: Option<usize>,
-    branches: Vec<MatchedDefinitionTree>,
+/// Evaluated definition and what happened while evaluating it
+#[derive(Debug, Default, PartialEq)]
+struct TraceNode {
+    /// Position of the definition in its level starting with 1
+    index: usize,
+    failed: Option<String>,
+    actions: Vec<String>,
+    sub_definitions: Vec<TraceNode>,
 }
 
-impl MatchedDefinitionTree {
-    pub fn new(id: usize) -> Self {
-        Self {
-            id: Some(id),
-            branches: Vec::new(),
-        }
+/// Tree of all evaluated definitions. Definitions after a `stop` or `ignore` are not evaluated and not part of the tree.
+#[derive(Debug, Default)]
+struct TraceTree {
+    definitions: Vec<TraceNode>,
+    /// Path to the definition whose sub definitions are currently evaluated
+    path: Vec<usize>,
+}
+
+impl TraceTree {
+    fn current_level(&mut self) -> &mut Vec<TraceNode> {
+        self.path.iter().fold(&mut self.definitions, |level, i| {
+            &mut level[*i].sub_definitions
+        })
+    }
+}
+
+impl Trace for TraceTree {
+    fn definition(&mut self, index: usize, failed: Option<&Conditions>) {
+        self.current_level().push(TraceNode {
+            index: index + 1,
+            failed: failed.map(|c| format!("{c:?}")),
+            ..Default::default()
+        });
     }
 
-    pub fn new_root() -> Self {
-        Self {
-            id: None,
-            branches: Vec::new(),
+    fn action(&mut self, action: &Action) {
+        if let Some(node) = self.current_level().last_mut() {
+            node.actions.push(format!("{action:?}"));
         }
     }
 
-    pub fn add_branch(&mut self, branch: MatchedDefinitionTree) {
-        self.branches.push(branch)
+    fn enter(&mut self) {
+        let last = self.current_level().len().saturating_sub(1);
+        self.path.push(last);
+    }
+
+    fn leave(&mut self) {
+        self.path.pop();
     }
 }
 
-///
-///     1
-///     |`1
-///
-///
-impl Display for MatchedDefinitionTree {
+/// ```text
+/// rule 1: matched
+/// |  Set(Group("mail"))
+/// |  rule 1: failed AppName("Thunderbird")
+/// rule 2: matched
+/// |  Stop
+/// ```
+impl Display for TraceTree {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        if let Some(id) = self.id {
-            writeln!(f, "{id}")?;
-            self.branches.iter().try_for_each(|b| writeln!(f, "|`{b}"))
-        } else {
-            writeln!(f)?;
-            self.branches.iter().try_for_each(|b| writeln!(f, "{b}"))
+        fn fmt_level(
+            f: &mut std::fmt::Formatter<'_>,
+            nodes: &[TraceNode],
+            depth: usize,
+        ) -> std::fmt::Result {
+            let indent = "|  ".repeat(depth);
+            for node in nodes {
+                match &node.failed {
+                    Some(condition) => {
+                        writeln!(f, "{indent}rule {}: failed {condition}", node.index)?
+                    }
+                    None => writeln!(f, "{indent}rule {}: matched", node.index)?,
+                }
+                for action in &node.actions {
+                    writeln!(f, "{indent}|  {action}")?;
+                }
+                fmt_level(f, &node.sub_definitions, depth + 1)?;
+            }
+            Ok(())
         }
+        fmt_level(f, &self.definitions, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use notify_server::notification::NotificationBuilder;
+
+    use super::TraceTree;
+    use crate::{
+        notification_bar::{NotificationData, NotificationTemplateData},
+        rule::{Action, Conditions, Definition, RuleExcutor, SetProperty},
+    };
+
+    #[test]
+    fn trace_nested_rules() {
+        let rules = vec![
+            Definition {
+                conditions: vec![Conditions::AppName("other".to_owned())],
+                ..Default::default()
+            },
+            Definition {
+                actions: vec![Action::Set(SetProperty::Group("g".to_owned()))],
+                sub_definition: vec![
+                    Definition {
+                        actions: vec![Action::Stop],
+                        ..Default::default()
+                    },
+                    Definition::default(),
+                ],
+                ..Default::default()
+            },
+            Definition::default(),
+        ];
+        let notification = NotificationBuilder::default()
+            .with_app_name("app".to_owned())
+            .build();
+        let mut nd = NotificationData::new(&notification, emoji::EmojiMode::Ignore);
+        let mut ntd = NotificationTemplateData::from(&notification);
+        let mut trace = TraceTree::default();
+        RuleExcutor::new(rules).trace(&notification, &mut ntd, &mut nd, &mut trace);
+
+        assert_eq!(
+            trace.to_string(),
+            r#"rule 1: failed AppName("other")
+rule 2: matched
+|  Set(Group("g"))
+|  rule 1: matched
+|  |  Stop
+"#
+        );
+        assert_eq!(nd.group.as_deref(), Some("g"));
     }
 }
@@ -0,0 +1,197 @@
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use log::{debug, warn};
+
+/// Application described by a `.desktop` file
+#[derive(Debug, PartialEq)]
+struct DesktopEntry {
+    /// File name without `.desktop`
+    id: String,
+    name: Option<String>,
+    /// Name of the executable from `Exec=` without path and arguments
+    exec: Option<String>,
+    wm_class: Option<String>,
+}
+
+/// Index of all installed applications used to map the different names applications
+/// use in notifications to the id of their desktop entry.
+#[derive(Debug, Default)]
+pub struct DesktopIndex {
+    entries: Vec<DesktopEntry>,
+}
+
+static INDEX: OnceLock<DesktopIndex> = OnceLock::new();
+
+/// Canonical id of the application that sent a notification.
+/// Falls back to the `desktop-entry` hint or the app name if no desktop entry matches.
+pub fn app_id(app_name: &str, desktop_entry: Option<&str>) -> String {
+    INDEX
+        .get_or_init(|| DesktopIndex::load(&data_dirs()))
+        .resolve(app_name, desktop_entry)
+        .or(desktop_entry)
+        .map(|id| id.trim_end_matches(".desktop"))
+        .unwrap_or(app_name)
+        .to_owned()
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`
+fn data_dirs() -> Vec<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/share")));
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| String::from("/usr/local/share:/usr/share"));
+    data_home
+        .into_iter()
+        .chain(data_dirs.split(':').map(PathBuf::from))
+        .collect()
+}
+
+impl DesktopIndex {
+    /// Reads `applications/*.desktop` in all `data_dirs`.
+    /// Entries in earlier directories hide entries with the same id in later ones.
+    pub fn load(data_dirs: &[PathBuf]) -> Self {
+        let mut index = Self::default();
+        for dir in data_dirs {
+            let Ok(files) = std::fs::read_dir(dir.join("applications")) else {
+                continue;
+            };
+            for path in files.filter_map(|f| f.ok()).map(|f| f.path()) {
+                if path.extension() != Some(OsStr::new("desktop")) {
+                    continue;
+                }
+                let Some(id) = path.file_stem().and_then(|s| s.to_str()) else {
+                    continue;
+                };
+                if index.entries.iter().any(|e| e.id == id) {
+                    continue;
+                }
+                match std::fs::read_to_string(&path) {
+                    Ok(content) => index.entries.push(parse_entry(id, &content)),
+                    Err(e) => warn!("Could not read desktop entry {:#?} error: {}", path, e),
+                }
+            }
+        }
+        debug!("Loaded {} desktop entries", index.entries.len());
+        index
+    }
+
+    /// Id of the desktop entry matching the `desktop-entry` hint, or else one whose id, `Name`,
+    /// executable or `StartupWMClass` equals `app_name` ignoring case.
+    pub fn resolve(&self, app_name: &str, desktop_entry: Option<&str>) -> Option<&str> {
+        let eq = |value: &Option<String>| {
+            value
+                .as_deref()
+                .is_some_and(|v| v.eq_ignore_ascii_case(app_name))
+        };
+        desktop_entry
+            .map(|hint| hint.trim_end_matches(".desktop"))
+            .and_then(|hint| {
+                self.entries
+                    .iter()
+                    .find(|e| e.id.eq_ignore_ascii_case(hint))
+            })
+            .or_else(|| {
+                self.entries
+                    .iter()
+                    .find(|e| e.id.eq_ignore_ascii_case(app_name))
+            })
+            .or_else(|| self.entries.iter().find(|e| eq(&e.name)))
+            .or_else(|| self.entries.iter().find(|e| eq(&e.exec)))
+            .or_else(|| self.entries.iter().find(|e| eq(&e.wm_class)))
+            .map(|e| e.id.as_str())
+    }
+}
+
+fn parse_entry(id: &str, content: &str) -> DesktopEntry {
+    let mut entry = DesktopEntry {
+        id: id.to_owned(),
+        name: None,
+        exec: None,
+        wm_class: None,
+    };
+    let mut in_main_group = false;
+    for line in content.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_main_group = line == "[Desktop Entry]";
+            continue;
+        }
+        if !in_main_group {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let value = value.trim().to_owned();
+        match key.trim() {
+            "Name" => entry.name = Some(value),
+            "Exec" => entry.exec = executable(&value),
+            "StartupWMClass" => entry.wm_class = Some(value),
+            _ => {}
+        }
+    }
+    entry
+}
+
+/// File name of the program started by an `Exec=` value. `env` and variable assignments are skipped.
+fn executable(exec: &str) -> Option<String> {
+    exec.split_whitespace()
+        .map(|arg| arg.trim_matches('"'))
+        .find(|arg| *arg != "env" && !arg.contains('='))
+        .and_then(|program| Path::new(program).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DesktopIndex;
+
+    #[test]
+    fn resolve_app_names() {
+        let dir =
+            std::env::temp_dir().join(format!("i3_notify_bar_desktop_test_{}", std::process::id()));
+        let applications = dir.join("applications");
+        std::fs::create_dir_all(&applications).unwrap();
+        std::fs::write(
+            applications.join("google-chrome.desktop"),
+            "[Desktop Entry]\nName=Google Chrome\nExec=/usr/bin/google-chrome-stable %U\nStartupWMClass=Chromium-browser\n[Desktop Action new-window]\nName=New Window\n",
+        )
+        .unwrap();
+        std::fs::write(
+            applications.join("org.mozilla.Thunderbird.desktop"),
+            "[Desktop Entry]\nName=Thunderbird\nExec=env MOZ_ENABLE_WAYLAND=1 thunderbird %u\n",
+        )
+        .unwrap();
+        std::fs::write(applications.join("README"), "Name=Readme").unwrap();
+
+        let index = DesktopIndex::load(std::slice::from_ref(&dir));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(index.resolve("Google Chrome", None), Some("google-chrome"));
+        assert_eq!(
+            index.resolve("google-chrome-stable", None),
+            Some("google-chrome")
+        );
+        assert_eq!(
+            index.resolve("chromium-browser", None),
+            Some("google-chrome")
+        );
+        assert_eq!(
+            index.resolve("notify-send", Some("google-chrome.desktop")),
+            Some("google-chrome")
+        );
+        assert_eq!(
+            index.resolve("thunderbird", None),
+            Some("org.mozilla.Thunderbird")
+        );
+        assert_eq!(index.resolve("New Window", None), None);
+        assert_eq!(index.resolve("Readme", None), None);
+    }
+}
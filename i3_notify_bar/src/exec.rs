@@ -0,0 +1,107 @@
+use std::process::{Command, Stdio};
+
+use log::{error, info, warn};
+use notify_server::notification::Urgency;
+
+use crate::notification_bar::NotificationData;
+
+/// Starts the command of an exec action without waiting for it. The output is written to the log.
+pub fn spawn(command: &[String], notification_data: &NotificationData) {
+    let Some((program, args)) = command.split_first() else {
+        return;
+    };
+    let child = Command::new(program)
+        .args(args)
+        .envs(environment(notification_data))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn();
+    let child = match child {
+        Ok(c) => c,
+        Err(e) => {
+            error!("Could not run {:?} error: {}", command, e);
+            return;
+        }
+    };
+    let command = command.join(" ");
+    std::thread::spawn(move || match child.wait_with_output() {
+        Ok(output) => {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .for_each(|l| info!("[{command}] {l}"));
+            String::from_utf8_lossy(&output.stderr)
+                .lines()
+                .for_each(|l| warn!("[{command}] {l}"));
+            if !output.status.success() {
+                warn!("[{command}] exited with {}", output.status);
+            }
+        }
+        Err(e) => error!("[{command}] {e}"),
+    });
+}
+
+/// Environment variables describing the notification
+fn environment(notification_data: &NotificationData) -> Vec<(&'static str, String)> {
+    let urgency = match notification_data.urgency {
+        Urgency::Low => "low",
+        Urgency::Normal => "normal",
+        Urgency::Critical => "critical",
+    };
+    vec![
+        ("I3_NOTIFY_ID", notification_data.id.to_string()),
+        ("I3_NOTIFY_APP_NAME", notification_data.app_name.clone()),
+        ("I3_NOTIFY_APP_ID", notification_data.app_id.clone()),
+        ("I3_NOTIFY_APP_ICON", notification_data.app_icon.clone()),
+        ("I3_NOTIFY_SUMMARY", notification_data.summary.clone()),
+        ("I3_NOTIFY_BODY", notification_data.body.clone()),
+        ("I3_NOTIFY_URGENCY", urgency.to_owned()),
+        (
+            "I3_NOTIFY_EXPIRE_TIMEOUT",
+            notification_data.expire_timeout.to_string(),
+        ),
+        ("I3_NOTIFY_TEXT", notification_data.plain_text()),
+        (
+            "I3_NOTIFY_GROUP",
+            notification_data.group.clone().unwrap_or_default(),
+        ),
+        (
+            "I3_NOTIFY_CATEGORY",
+            notification_data.hints.category.clone().unwrap_or_default(),
+        ),
+        ("I3_NOTIFY_LINKS", notification_data.links.join("\n")),
+        (
+            "I3_NOTIFY_DESKTOP_ENTRY",
+            notification_data
+                .hints
+                .desktop_entry
+                .clone()
+                .unwrap_or_default(),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use notify_server::notification::{NotificationBuilder, Urgency};
+
+    use crate::notification_bar::NotificationData;
+
+    #[test]
+    fn environment() {
+        let notification = NotificationBuilder::default()
+            .with_app_name("mail".to_owned())
+            .with_summary("New mail".to_owned())
+            .with_urgency(Urgency::Critical)
+            .build();
+        let mut nd = NotificationData::new(&notification, emoji::EmojiMode::Ignore);
+        nd.group = Some("Mail".to_owned());
+        let env = super::environment(&nd);
+        let get = |key| env.iter().find(|(k, _)| *k == key).map(|(_, v)| v.as_str());
+        assert_eq!(get("I3_NOTIFY_APP_NAME"), Some("mail"));
+        assert_eq!(get("I3_NOTIFY_SUMMARY"), Some("New mail"));
+        assert_eq!(get("I3_NOTIFY_URGENCY"), Some("critical"));
+        assert_eq!(get("I3_NOTIFY_GROUP"), Some("Mail"));
+        assert_eq!(get("I3_NOTIFY_CATEGORY"), Some(""));
+    }
+}
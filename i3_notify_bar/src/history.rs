@@ -0,0 +1,134 @@
+use std::collections::VecDeque;
+
+use notify_server::{
+    notification::{Action, Urgency},
+    NotificationId,
+};
+
+use crate::notification_bar::NotificationData;
+
+/// Bounded list of closed notifications. Once full the oldest entry is dropped.
+#[derive(Debug, Default)]
+pub struct NotificationHistory {
+    entries: VecDeque<HistoryEntry>,
+    capacity: usize,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub id: NotificationId,
+    pub app_name: String,
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+    pub icon: char,
+    pub text: String,
+    pub group: Option<String>,
+    pub time: i64,
+    pub actions: Vec<Action>,
+    /// Unique bus name of the sending application. Actions can only be invoked while it is connected.
+    pub sender: Option<String>,
+}
+
+impl NotificationHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, entry: HistoryEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+        while self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// Iterates over all entries starting with the most recent one.
+    pub fn iter(&self) -> impl Iterator<Item = &HistoryEntry> {
+        self.entries.iter().rev()
+    }
+}
+
+impl From<&NotificationData> for HistoryEntry {
+    fn from(nd: &NotificationData) -> Self {
+        Self {
+            id: nd.id,
+            app_name: nd.app_name.clone(),
+            summary: nd.summary.clone(),
+            body: nd.body.clone(),
+            urgency: nd.urgency,
+            icon: nd.icon,
+            text: nd.plain_text(),
+            group: nd.group.clone(),
+            time: nd.time,
+            actions: nd.actions.clone(),
+            sender: nd.sender.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryEntry, NotificationHistory};
+
+    fn entry(id: u32) -> HistoryEntry {
+        HistoryEntry {
+            id: id.into(),
+            app_name: Default::default(),
+            summary: Default::default(),
+            body: Default::default(),
+            urgency: Default::default(),
+            icon: ' ',
+            text: Default::default(),
+            group: None,
+            time: 0,
+            actions: Vec::new(),
+            sender: None,
+        }
+    }
+
+    #[test]
+    fn push_drops_oldest_entry() {
+        let mut history = NotificationHistory::new(2);
+        history.push(entry(1));
+        history.push(entry(2));
+        history.push(entry(3));
+        assert_eq!(history.iter().count(), 2);
+        assert_eq!(
+            history.iter().map(|e| e.id).collect::<Vec<_>>(),
+            vec![3.into(), 2.into()]
+        );
+    }
+
+    #[test]
+    fn entry_keeps_actions_and_sender() {
+        use notify_server::notification::{Action, NotificationBuilder};
+
+        let action = Action {
+            key: "default".into(),
+            text: "Open".into(),
+        };
+        let notification = NotificationBuilder::default()
+            .with_actions(vec![action.clone()])
+            .with_sender(Some(":1.42".into()))
+            .build();
+        let entry = HistoryEntry::from(&crate::notification_bar::NotificationData::new(
+            &notification,
+            emoji::EmojiMode::Ignore,
+        ));
+        assert_eq!(entry.actions, vec![action]);
+        assert_eq!(entry.sender.as_deref(), Some(":1.42"));
+    }
+
+    #[test]
+    fn push_without_capacity() {
+        let mut history = NotificationHistory::new(0);
+        history.push(entry(1));
+        assert_eq!(history.iter().count(), 0);
+    }
+}
@@ -0,0 +1,171 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::PathBuf,
+};
+
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{history::HistoryEntry, notification_bar::NotificationData};
+
+/// Persists notifications as JSON lines so they survive a restart of the bar.
+pub struct HistoryStore {
+    path: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredNotification {
+    /// `true` if the notification was still shown when it was stored
+    pub active: bool,
+    pub app_name: String,
+    pub summary: String,
+    pub body: String,
+    pub urgency: u8,
+    pub time: i64,
+    pub group: Option<String>,
+    pub icon: char,
+    pub text: String,
+    /// Unix timestamp at which an active notification expires
+    pub expires_at: Option<i64>,
+}
+
+impl HistoryStore {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn load(&self) -> Vec<StoredNotification> {
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Vec::new(),
+            Err(e) => {
+                error!(
+                    "Could not open history file {:#?} error: {:#?}",
+                    self.path, e
+                );
+                return Vec::new();
+            }
+        };
+        BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| match serde_json::from_str(&line) {
+                Ok(n) => Some(n),
+                Err(e) => {
+                    warn!("Skipping invalid history entry {line}: {e}");
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub fn save(&self, notifications: &[StoredNotification]) -> std::io::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // Write to a temporary file first to not lose the history if the bar is killed while writing
+        let tmp_path = self.path.with_extension("tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        for notification in notifications {
+            serde_json::to_writer(&mut writer, notification)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+        std::fs::rename(tmp_path, &self.path)
+    }
+}
+
+impl StoredNotification {
+    pub fn from_active(nd: &NotificationData, now: i64) -> Self {
+        Self {
+            active: true,
+            app_name: nd.app_name.clone(),
+            summary: nd.summary.clone(),
+            body: nd.body.clone(),
+            urgency: nd.urgency as u8,
+            time: nd.time,
+            group: nd.group.clone(),
+            icon: nd.icon,
+            text: nd.plain_text(),
+            expires_at: nd.remove_in_secs.map(|rm| now + rm.ceil() as i64),
+        }
+    }
+}
+
+impl From<&HistoryEntry> for StoredNotification {
+    fn from(entry: &HistoryEntry) -> Self {
+        Self {
+            active: false,
+            app_name: entry.app_name.clone(),
+            summary: entry.summary.clone(),
+            body: entry.body.clone(),
+            urgency: entry.urgency as u8,
+            time: entry.time,
+            group: entry.group.clone(),
+            icon: entry.icon,
+            text: entry.text.clone(),
+            expires_at: None,
+        }
+    }
+}
+
+impl From<StoredNotification> for HistoryEntry {
+    fn from(stored: StoredNotification) -> Self {
+        Self {
+            // The sending application does not know about this notification anymore.
+            id: 0.into(),
+            app_name: stored.app_name,
+            summary: stored.summary,
+            body: stored.body,
+            urgency: stored.urgency.into(),
+            icon: stored.icon,
+            text: stored.text,
+            group: stored.group,
+            time: stored.time,
+            actions: Vec::new(),
+            sender: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HistoryStore, StoredNotification};
+
+    fn stored(summary: &str) -> StoredNotification {
+        StoredNotification {
+            active: false,
+            app_name: "app".into(),
+            summary: summary.into(),
+            body: "body".into(),
+            urgency: 1,
+            time: 1700000000,
+            group: None,
+            icon: ' ',
+            text: summary.into(),
+            expires_at: None,
+        }
+    }
+
+    #[test]
+    fn save_and_load() {
+        let dir = std::env::temp_dir().join(format!("i3_notify_bar_test_{}", std::process::id()));
+        let store = HistoryStore::new(dir.join("history.jsonl"));
+        let mut active = stored("active");
+        active.active = true;
+        active.expires_at = Some(1700000010);
+        let notifications = vec![stored("old"), active];
+        store.save(&notifications).unwrap();
+        assert_eq!(store.load(), notifications);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn load_missing_file() {
+        let store = HistoryStore::new("/this/file/does/not/exist.jsonl".into());
+        assert!(store.load().is_empty());
+    }
+}
@@ -1,22 +1,95 @@
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{OnceLock, RwLock},
+};
+
+use log::{info, warn};
+
 macro_rules! icons {
     ($($name: tt => $icon_name: ident $(= $char: literal)?),*) => {
         $($(
             pub const $icon_name: char = $char;
         )?)*
 
-        pub fn get_icon(name: &str) -> Option<char> {
-            let c = match name {
-                $(
-                    $name => $icon_name,
-                )*
-                _ => return None
-            };
+        const BUILTIN_ICONS: &[(&str, char)] = &[$(($name, $icon_name)),*];
+    };
+}
 
-            Some(c)
-        }
+static ICONS: OnceLock<RwLock<HashMap<String, char>>> = OnceLock::new();
 
+fn icons() -> &'static RwLock<HashMap<String, char>> {
+    ICONS.get_or_init(|| {
+        RwLock::new(
+            BUILTIN_ICONS
+                .iter()
+                .map(|(name, icon)| (name.to_string(), *icon))
+                .collect(),
+        )
+    })
+}
 
+/// Adds the icons of `icon_file` to the built-in icons. Icons in the file replace built-in icons with the same name.
+///
+/// Each line contains a name followed by the icon. The icon is either the glyph itself
+/// or its code point like `U+F392`. Names may contain spaces. Lines starting with `#` are ignored.
+pub fn init(icon_file: Option<&Path>) {
+    let Some(icon_file) = icon_file else {
+        return;
     };
+    let content = match std::fs::read_to_string(icon_file) {
+        Ok(c) => c,
+        Err(e) => {
+            info!("Could not read icon file {:#?} error: {}", icon_file, e);
+            return;
+        }
+    };
+    let mut icons = icons().write().unwrap_or_else(|e| e.into_inner());
+    for (line_number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let icon = line
+            .rsplit_once(char::is_whitespace)
+            .and_then(|(name, icon)| Some((name.trim(), parse_glyph(icon)?)));
+        match icon {
+            Some((name, icon)) => {
+                icons.insert(name.to_owned(), icon);
+            }
+            None => warn!(
+                "Invalid icon in {:#?} line {}: {}",
+                icon_file,
+                line_number + 1,
+                line
+            ),
+        }
+    }
+}
+
+pub fn get_icon(name: &str) -> Option<char> {
+    icons()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(name)
+        .copied()
+}
+
+/// Icon from the name of a known icon, a code point like `U+F392` or a single glyph.
+/// Names take precedence so single character names like `x` stay reachable.
+pub fn parse_icon(value: &str) -> Option<char> {
+    get_icon(value).or_else(|| parse_glyph(value))
+}
+
+fn parse_glyph(value: &str) -> Option<char> {
+    if let Some(code_point) = value.strip_prefix("U+").or(value.strip_prefix("u+")) {
+        return char::from_u32(u32::from_str_radix(code_point, 16).ok()?);
+    }
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Some(c),
+        _ => None,
+    }
 }
 
 #[cfg(feature = "fa_icons")]
@@ -31,7 +104,11 @@ icons!(
     "Thunderbird" => MAIL_ICON,
     "x" => X_ICON = '\u{F057}',
     "menu" => MENU_ICON = '\u{F0C9}',
-    "close" => CLOSE_ICON = '\u{F00D}'
+    "history" => HISTORY_ICON = '\u{F1DA}',
+    "queued" => QUEUED_ICON = '\u{F1F6}',
+    "warning" => WARNING_ICON = '\u{F071}',
+    "close" => CLOSE_ICON = '\u{F00D}',
+    "copy" => COPY_ICON = '\u{F0C5}'
 );
 
 #[cfg(not(feature = "fa_icons"))]
@@ -48,8 +125,35 @@ mod tests {
             "Test" => TEST_ICON = 'w'
         );
 
-        assert_eq!(get_icon("Test"), Some('w'));
-        assert_eq!(get_icon(""), None);
+        assert_eq!(BUILTIN_ICONS, [("Test", 'w')]);
         assert_eq!(TEST_ICON, 'w');
     }
+
+    #[test]
+    fn parse_icon() {
+        #[cfg(feature = "fa_icons")]
+        assert_eq!(super::parse_icon("x"), Some('\u{F057}'));
+        assert_eq!(super::parse_icon("a"), Some('a'));
+        assert_eq!(super::parse_icon("U+F392"), Some('\u{F392}'));
+        assert_eq!(super::parse_icon("\u{F392}"), Some('\u{F392}'));
+        assert_eq!(super::parse_icon("unknown"), None);
+        assert_eq!(super::parse_icon("U+ZZZZ"), None);
+    }
+
+    #[test]
+    fn icon_file() {
+        let path =
+            std::env::temp_dir().join(format!("i3_notify_bar_icons_test_{}", std::process::id()));
+        std::fs::write(
+            &path,
+            "# comment\nGoogle Chrome U+F268\nTest \u{F0E0}\ninvalid\n",
+        )
+        .unwrap();
+        super::init(Some(&path));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(super::get_icon("Google Chrome"), Some('\u{F268}'));
+        assert_eq!(super::get_icon("Test"), Some('\u{F0E0}'));
+        assert_eq!(super::get_icon("invalid"), None);
+        assert_eq!(super::parse_icon("Google Chrome"), Some('\u{F268}'));
+    }
 }
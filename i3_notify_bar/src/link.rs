@@ -0,0 +1,101 @@
+use std::{
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use log::error;
+
+pub const DEFAULT_OPENER: &str = "xdg-open";
+
+static OPENER: OnceLock<Vec<String>> = OnceLock::new();
+
+/// Sets the command used to open links. The link is passed as last argument.
+/// Only the first call has an effect.
+pub fn set_opener(opener: Vec<String>) {
+    let _ = OPENER.set(opener);
+}
+
+/// Schemes of links which are offered and opened
+const ALLOWED_SCHEMES: [&str; 3] = ["http://", "https://", "mailto:"];
+
+/// Only web and mail links are opened. Other schemes could start arbitrary programs through the opener.
+/// As all allowed links start with a scheme they can not be mistaken for an option of the opener.
+/// `xdg-open` rejects `--`, other openers may be configured with a trailing `--`.
+pub fn is_allowed(url: &str) -> bool {
+    ALLOWED_SCHEMES.iter().any(|scheme| {
+        url.get(..scheme.len())
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// Opens `url` with the configured opener without waiting for it
+pub fn open(url: &str) {
+    if !is_allowed(url) {
+        error!("Refusing to open link {url}");
+        return;
+    }
+    let opener = OPENER.get_or_init(|| vec![DEFAULT_OPENER.to_owned()]);
+    let Some((program, args)) = opener.split_first() else {
+        return;
+    };
+    let child = Command::new(program)
+        .args(args)
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        Ok(mut child) => {
+            std::thread::spawn(move || child.wait());
+        }
+        Err(e) => error!("Could not open {} error: {}", url, e),
+    }
+}
+
+/// Finds http and https URLs in plain text. Trailing punctuation is not part of the URL.
+pub fn find_urls(text: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let mut rest = text;
+    while let Some(start) = ["https://", "http://"]
+        .iter()
+        .filter_map(|scheme| rest.find(scheme))
+        .min()
+    {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '<' | '>' | '"' | '\''))
+            .unwrap_or(candidate.len());
+        let url = candidate[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']']);
+        if !url.ends_with("://") {
+            urls.push(url.to_owned());
+        }
+        rest = &candidate[end..];
+    }
+    urls
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_urls, is_allowed};
+
+    #[test]
+    fn find_bare_urls() {
+        assert_eq!(
+            find_urls("Build failed: https://ci.example.com/job/1. See (http://example.com/log) or https://"),
+            ["https://ci.example.com/job/1", "http://example.com/log"]
+        );
+        assert!(find_urls("no links here").is_empty());
+    }
+
+    #[test]
+    fn allowed_links() {
+        assert!(is_allowed("https://example.com"));
+        assert!(is_allowed("HTTP://example.com"));
+        assert!(is_allowed("mailto:someone@example.com"));
+        assert!(!is_allowed("file:///etc/passwd"));
+        assert!(!is_allowed("--help"));
+        assert!(!is_allowed("javascript:alert(1)"));
+        assert!(!is_allowed("http"));
+    }
+}
@@ -1,39 +1,66 @@
 #![deny(clippy::unwrap_used)]
 mod args;
+mod clipboard;
 mod components;
 mod config_parser;
-// Currently disabled
-//mod debug_config;
+mod config_watcher;
+mod control;
+mod debug_config;
+mod desktop_entry;
+mod exec;
+mod history;
+mod history_store;
 mod icons;
+mod link;
+mod markup;
 mod notification_bar;
+mod output;
 mod path_manager;
+mod replay;
 mod rule;
+mod schedule;
 mod template;
+mod waybar;
 
 use args::Args;
-use components::NotificationBar;
+use components::{ConfigError, MenuState, NotificationBar};
+use config_parser::Config;
+use config_watcher::ConfigWatcher;
 use emoji::EmojiMode;
+use history::NotificationHistory;
+use history_store::HistoryStore;
 use i3_bar_components::{
     component_manager::{ComponentManagerBuilder, ManageComponents},
     components::{prelude::Urgent, Label},
+    protocol::ClickEvent,
     string::AnimatedString,
 };
-use log::{debug, error};
-use notification_bar::{MinimalUrgency, NotificationEvent, NotificationManager};
+use log::{debug, error, info};
+use markup::MarkupMode;
+use notification_bar::{MinimalUrgency, NotificationEvent, NotificationManager, SuppressMode};
+use output::{Bar, OutputMode};
 use path_manager::PathManager;
 use rule::{Definition, RuleExcutor};
+use schedule::Scheduler;
 use std::{
     io::BufReader,
-    path::Path,
+    path::{Path, PathBuf},
     sync::{Arc, RwLock},
     time::Duration,
 };
+use waybar::Waybar;
 
 #[macro_use]
 extern crate pest_derive;
 
 pub enum SystemCommand {
     ForceUpdate,
+    /// Click reported by bars without click events on stdin. `handled` receives if the output accepts clicks.
+    Click {
+        instance: usize,
+        button: usize,
+        handled: std::sync::mpsc::Sender<bool>,
+    },
 }
 
 #[async_std::main]
@@ -42,15 +69,28 @@ async fn main() {
     let Args {
         emoji_mode,
         emoji_file,
+        icon_file,
         log_level,
         log_file,
         refresh_rate,
         max_text_length,
         animation_chars_per_second,
+        history_size,
+        history_file,
+        persist_history,
+        socket_file,
+        record_file,
+        clipboard_command,
+        link_opener,
+        markup_mode,
+        output_mode,
         config_file,
         command,
     } = args::load();
 
+    clipboard::set_command(clipboard_command);
+    link::set_opener(link_opener);
+
     if let Some(file) = config_file {
         path_manager.set_config_file(file)
     }
@@ -63,10 +103,38 @@ async fn main() {
         path_manager.set_emoji_file(file);
     }
 
+    if let Some(file) = icon_file {
+        path_manager.set_icon_file(file);
+    }
+
+    if let Some(file) = history_file {
+        path_manager.set_history_file(file);
+    }
+
+    if let Some(file) = socket_file {
+        path_manager.set_socket_file(file);
+    }
+
+    // Control commands only talk to the running instance. Bars run them on every click, so they skip
+    // loading the config, icons and emojis.
+    let command = match command {
+        args::Command::Ctl(cmd) => {
+            return ctl(path_manager.socket_file().map(Path::to_path_buf), cmd);
+        }
+        command => command,
+    };
+
     logger::init(log_level, path_manager.log_file());
 
-    let config = read_config(path_manager.config_file());
+    icons::init(path_manager.icon_file());
+    let (config, config_errors) = read_config(path_manager.config_file());
     emoji::init(path_manager.emoji_file().as_ref().map(Path::new));
+    let history_file = path_manager
+        .history_file()
+        .filter(|_| persist_history)
+        .map(Path::to_path_buf);
+    let socket_file = path_manager.socket_file().map(Path::to_path_buf);
+    let config_file = path_manager.config_file().map(Path::to_path_buf);
 
     drop(path_manager);
 
@@ -74,57 +142,146 @@ async fn main() {
         args::Command::Run => {
             run(
                 config,
+                config_errors,
+                config_file,
                 emoji_mode,
                 max_text_length,
                 animation_chars_per_second,
                 refresh_rate,
+                history_size,
+                history_file,
+                socket_file,
+                record_file,
+                markup_mode,
+                output_mode,
             )
             .await
         }
-        args::Command::DebugConfig(_) => eprintln!("Currently disabled"),
-        // args::Command::DebugConfig(dc) => debug_config::debug_config(&config, emoji_mode, dc),
+        args::Command::Ctl(_) => unreachable!("Control commands are handled before the setup"),
+        args::Command::Replay(file) => replay(&file, config, config_errors, emoji_mode),
+        args::Command::DebugConfig(dc) => {
+            config_errors.iter().for_each(|e| eprintln!("{e}"));
+            debug_config::debug_config(config.definitions, emoji_mode, *dc)
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn run(
-    config: Vec<Definition>,
+    config: Config,
+    config_errors: Vec<String>,
+    config_file: Option<PathBuf>,
     emoji_mode: EmojiMode,
     max_text_length: usize,
     animation_chars_per_second: usize,
     refresh_rate: u64,
+    history_size: usize,
+    history_file: Option<PathBuf>,
+    socket_file: Option<PathBuf>,
+    record_file: Option<PathBuf>,
+    markup_mode: MarkupMode,
+    output_mode: OutputMode,
 ) {
     let (system_command_tx, system_command_rx) = std::sync::mpsc::channel();
     let minimal_urgency = Arc::new(RwLock::new(MinimalUrgency::Normal));
-
-    let mut component_manager = ComponentManagerBuilder::new()
-        .with_click_events(true)
-        .build();
-
-    component_manager.set_global_event_listener(|_, ce| {
-        debug!("{}", ce.get_button().to_string());
-    });
+    let mut scheduler = Scheduler::new(config.schedule, Arc::clone(&minimal_urgency));
+    let suppress_mode = Arc::new(RwLock::new(SuppressMode::default()));
+    let history = Arc::new(RwLock::new(NotificationHistory::new(history_size)));
+    let config_errors = Arc::new(RwLock::new(config_errors));
+    let mut config_watcher = config_file.map(ConfigWatcher::new);
 
     let notify_server =
         notify_server::NotifyServer::start().expect("Error starting notification server.");
+    let rule_executor = RuleExcutor::new(config.definitions);
+    let rules = rule_executor.shared_rules();
     let mut notification_manager = NotificationManager::new(
         emoji_mode,
         Arc::clone(&minimal_urgency),
+        Arc::clone(&suppress_mode),
+        Arc::clone(&history),
         notify_server,
-        RuleExcutor::new(config),
+        rule_executor,
     );
+    notification_manager.set_markup_mode(markup_mode);
 
-    component_manager.add_component(Box::new(NotificationBar::new(
-        minimal_urgency,
-        notification_manager.linked_commands(),
-        notification_manager.event_channel(),
-        max_text_length,
-        animation_chars_per_second,
-        system_command_tx,
-    )));
+    if let Some(file) = record_file {
+        match replay::Recorder::new(&file) {
+            Ok(recorder) => notification_manager.set_recorder(recorder),
+            Err(e) => error!("Could not open record file {:#?} error: {}", file, e),
+        }
+    }
+
+    if let Some(file) = history_file {
+        notification_manager.set_history_store(HistoryStore::new(file));
+    }
+
+    let exe = std::env::current_exe().unwrap_or_else(|_| PathBuf::from("i3_notify_bar"));
+    let click_command = output::click_command(&exe, socket_file.as_deref());
+    if let Some(file) = socket_file {
+        if let Err(e) = control::start_server(
+            &file,
+            notification_manager.linked_commands(),
+            system_command_tx.clone(),
+        ) {
+            error!("Could not start control socket at {:#?} error: {}", file, e);
+        }
+    }
+
+    let (click_tx, click_rx) = std::sync::mpsc::channel();
+    let mut bar: Box<dyn Bar> = match output_mode {
+        OutputMode::Components(output) => {
+            let output = match output.create(&click_command, click_rx) {
+                Ok(output) => output,
+                Err(e) => {
+                    error!("Could not start output {:?} error: {}", output_mode, e);
+                    return;
+                }
+            };
+            let mut component_manager = ComponentManagerBuilder::new()
+                .with_click_events(true)
+                .with_output(output)
+                .build();
+
+            component_manager.set_global_event_listener(|_, ce| {
+                debug!("{}", ce.get_button().to_string());
+            });
+
+            component_manager.add_component(Box::new(ConfigError::new(
+                Arc::clone(&config_errors),
+                max_text_length,
+                animation_chars_per_second,
+            )));
+            component_manager.add_component(Box::new(NotificationBar::new(
+                MenuState {
+                    minimal_urgency,
+                    suppress_mode,
+                    history,
+                },
+                notification_manager.linked_commands(),
+                notification_manager.event_channel(),
+                max_text_length,
+                animation_chars_per_second,
+                system_command_tx.clone(),
+            )));
+            Box::new(component_manager)
+        }
+        OutputMode::Waybar => Box::new(Waybar::new(
+            std::io::stdout(),
+            notification_manager.event_channel(),
+            minimal_urgency,
+            Arc::clone(&config_errors),
+        )),
+    };
 
     let mut last_update = std::time::SystemTime::now();
 
     loop {
+        if let Some(watcher) = config_watcher.as_mut() {
+            if watcher.changed() {
+                reload_config(watcher.path(), &rules, &mut scheduler, &config_errors);
+            }
+        }
+        scheduler.update(chrono::Local::now().naive_local());
         notification_manager
             .update(
                 last_update
@@ -135,37 +292,140 @@ async fn run(
             .await;
         last_update = std::time::SystemTime::now();
 
-        component_manager.update();
+        bar.update();
         match system_command_rx.recv_timeout(Duration::from_millis(refresh_rate)) {
             Ok(SystemCommand::ForceUpdate) => {}
+            Ok(SystemCommand::Click {
+                instance,
+                button,
+                handled,
+            }) => {
+                // The receiver is dropped by outputs which do not support clicks
+                let sent = click_tx.send(ClickEvent::new(instance, button)).is_ok();
+                handled.send(sent).ok();
+            }
             Err(_) => {}
         }
     }
 }
 
-fn read_config(config_file: Option<&Path>) -> Vec<crate::rule::Definition> {
-    match config_file {
-        Some(path) => {
-            let config_file = match std::fs::File::open(path) {
-                Ok(f) => f,
-                Err(e) => {
-                    error!("Could not open file {:#?} error: {:#?}", path, e);
-                    return Vec::new();
-                }
-            };
-            let mut config_file = BufReader::new(config_file);
-            match rule::parse_config(&mut config_file) {
-                Ok(r) => r,
-                Err(e) => {
-                    error!("{}", e.to_string());
-                    print_error(e.to_string());
+/// Replaces the rules and schedule with the ones in `path`. The old rules are kept if the config is invalid.
+fn reload_config(
+    path: &Path,
+    rules: &RwLock<Vec<Definition>>,
+    scheduler: &mut Scheduler,
+    config_errors: &RwLock<Vec<String>>,
+) {
+    match load_config(path) {
+        Ok(config) => {
+            info!("Reloaded config {:#?}", path);
+            *rules.write().unwrap_or_else(|e| e.into_inner()) = config.definitions;
+            scheduler.set_entries(config.schedule);
+            config_errors
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .clear();
+        }
+        Err((_, errors)) => {
+            *config_errors.write().unwrap_or_else(|e| e.into_inner()) = errors;
+        }
+    }
+    // Drop the templates of the replaced or rejected rules
+    template::retain_templates(&rule::template_ids(
+        &rules.read().unwrap_or_else(|e| e.into_inner()),
+    ));
+}
+
+fn ctl(socket_file: Option<PathBuf>, command: control::ControlCommand) {
+    let Some(socket_file) = socket_file else {
+        eprintln!("Control socket location unknown. Use --socket-file");
+        std::process::exit(1);
+    };
+    match control::send(&socket_file, &command) {
+        Ok(response) if response.ok => {
+            if let Some(notifications) = response.notifications {
+                match serde_json::to_string_pretty(&notifications) {
+                    Ok(n) => println!("{n}"),
+                    Err(e) => eprintln!("{e}"),
                 }
             }
         }
-        None => Vec::new(),
+        Ok(response) => {
+            eprintln!("{}", response.error.unwrap_or_default());
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Could not connect to {:#?}: {}", socket_file, e);
+            std::process::exit(1);
+        }
     }
 }
 
+/// Exits with 1 if the config or the recorded notifications contain errors
+fn replay(file: &Path, config: Config, config_errors: Vec<String>, emoji_mode: EmojiMode) {
+    config_errors.iter().for_each(|e| eprintln!("{e}"));
+    let mut input: Box<dyn std::io::BufRead> = if file == Path::new("-") {
+        Box::new(std::io::stdin().lock())
+    } else {
+        match std::fs::File::open(file) {
+            Ok(f) => Box::new(BufReader::new(f)),
+            Err(e) => {
+                eprintln!("Could not open {:#?}: {}", file, e);
+                std::process::exit(1);
+            }
+        }
+    };
+    let rules = RuleExcutor::new(config.definitions);
+    match replay::replay(
+        &mut input,
+        &rules,
+        emoji_mode,
+        &mut std::io::stdout().lock(),
+    ) {
+        Ok(0) if config_errors.is_empty() => {}
+        Ok(_) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reads the config and returns its valid part together with all errors.
+fn read_config(config_file: Option<&Path>) -> (Config, Vec<String>) {
+    let Some(path) = config_file else {
+        return (Config::default(), Vec::new());
+    };
+    if let Err(e) = std::fs::metadata(path) {
+        error!("Could not open file {:#?} error: {:#?}", path, e);
+        return (Config::default(), Vec::new());
+    }
+    match load_config(path) {
+        Ok(config) => (config, Vec::new()),
+        Err(invalid) => invalid,
+    }
+}
+
+/// On errors the valid part of the config is returned together with the error messages.
+fn load_config(path: &Path) -> Result<Config, (Config, Vec<String>)> {
+    let config_file = std::fs::File::open(path).map_err(|e| {
+        let e = format!("Could not open file {:#?} error: {}", path, e);
+        error!("{}", e);
+        (Config::default(), vec![e])
+    })?;
+    rule::parse_config(&mut BufReader::new(config_file)).map_err(|invalid| {
+        let errors = invalid
+            .errors
+            .iter()
+            .map(|e| {
+                error!("Config error {}", e);
+                format!("{}:{}", path.display(), e)
+            })
+            .collect();
+        (invalid.config, errors)
+    })
+}
+
 fn print_error(data: String) -> ! {
     let mut cm = ComponentManagerBuilder::new()
         .with_click_events(false)
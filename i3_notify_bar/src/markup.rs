@@ -0,0 +1,348 @@
+use i3_bar_components::string::{AnimatedString, ComponentString};
+
+/// How markup in notification bodies is shown
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MarkupMode {
+    /// Remove all tags and show plain text
+    #[default]
+    Strip,
+    /// Convert to pango markup. Bold, italic and underline are rendered by i3bar.
+    Pango,
+}
+
+impl std::str::FromStr for MarkupMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "strip" => MarkupMode::Strip,
+            "pango" => MarkupMode::Pango,
+            _ => return Err(format!("Can not convert {} to markup mode", s)),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+struct Format {
+    bold: bool,
+    italic: bool,
+    underline: bool,
+}
+
+/// Text parsed from the markup subset of the notification specification.
+/// Supported tags are `<b>`, `<i>`, `<u>`, `<a href="...">` and `<img alt="...">`.
+/// Unknown tags are removed.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Markup {
+    runs: Vec<(String, Format)>,
+    links: Vec<String>,
+}
+
+impl Markup {
+    pub fn parse(text: &str) -> Self {
+        let mut markup = Markup::default();
+        let (mut bold, mut italic, mut underline) = (0usize, 0usize, 0usize);
+        let mut rest = text;
+        while let Some(c) = rest.chars().next() {
+            let format = Format {
+                bold: bold > 0,
+                italic: italic > 0,
+                underline: underline > 0,
+            };
+            match c {
+                '<' => {
+                    let Some(end) = rest.find('>') else {
+                        // Not a tag
+                        markup.push(rest, format);
+                        break;
+                    };
+                    let tag = rest[1..end].trim();
+                    rest = &rest[end + 1..];
+                    let (closing, tag) = match tag.strip_prefix('/') {
+                        Some(tag) => (true, tag.trim()),
+                        None => (false, tag.trim_end_matches('/')),
+                    };
+                    let name = tag
+                        .split_whitespace()
+                        .next()
+                        .unwrap_or_default()
+                        .to_lowercase();
+                    let counter = match name.as_str() {
+                        "b" => &mut bold,
+                        "i" => &mut italic,
+                        "u" => &mut underline,
+                        "a" if !closing => {
+                            if let Some(href) = attribute(tag, "href") {
+                                markup.links.push(href);
+                            }
+                            continue;
+                        }
+                        "img" => {
+                            if let Some(alt) = attribute(tag, "alt") {
+                                markup.push(&alt, format);
+                            }
+                            continue;
+                        }
+                        "br" => {
+                            markup.push("\n", format);
+                            continue;
+                        }
+                        _ => continue,
+                    };
+                    if closing {
+                        *counter = counter.saturating_sub(1);
+                    } else {
+                        *counter += 1;
+                    }
+                }
+                '&' => {
+                    let (text, len) = entity(rest).unwrap_or(('&', 1));
+                    markup.push(text.encode_utf8(&mut [0; 4]), format);
+                    rest = &rest[len..];
+                }
+                _ => {
+                    let len = rest.find(['<', '&']).unwrap_or(rest.len());
+                    markup.push(&rest[..len], format);
+                    rest = &rest[len..];
+                }
+            }
+        }
+        markup
+    }
+
+    fn push(&mut self, text: &str, format: Format) {
+        match self.runs.last_mut() {
+            Some((last, f)) if *f == format => last.push_str(text),
+            _ => self.runs.push((text.to_owned(), format)),
+        }
+    }
+
+    /// Text without any markup
+    pub fn strip(&self) -> String {
+        self.runs.iter().map(|(text, _)| text.as_str()).collect()
+    }
+
+    /// Number of visible chars
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|(text, _)| text.chars().count()).sum()
+    }
+
+    /// Targets of all `<a>` tags in order of appearance
+    pub fn links(&self) -> &[String] {
+        &self.links
+    }
+
+    pub fn to_pango(&self) -> String {
+        self.slice(0, self.len())
+    }
+
+    /// Pango markup of `len` visible chars starting at `start`. Tags are closed at the end of the slice.
+    pub fn slice(&self, start: usize, len: usize) -> String {
+        let mut out = String::new();
+        let mut skip = start;
+        let mut remaining = len;
+        for (text, format) in &self.runs {
+            if remaining == 0 {
+                break;
+            }
+            let count = text.chars().count();
+            if skip >= count {
+                skip -= count;
+                continue;
+            }
+            let part = text.chars().skip(skip).take(remaining).collect::<String>();
+            skip = 0;
+            remaining -= part.chars().count();
+            let tags = [
+                (format.bold, "b"),
+                (format.italic, "i"),
+                (format.underline, "u"),
+            ];
+            tags.iter()
+                .filter(|(set, _)| *set)
+                .for_each(|(_, tag)| out.push_str(&format!("<{tag}>")));
+            out.push_str(&escape(&part));
+            tags.iter()
+                .rev()
+                .filter(|(set, _)| *set)
+                .for_each(|(_, tag)| out.push_str(&format!("</{tag}>")));
+        }
+        out
+    }
+}
+
+/// Escapes text for use in pango markup
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Decodes the entity at the start of `text`. Returns the char and the length of the entity.
+fn entity(text: &str) -> Option<(char, usize)> {
+    let end = text.get(..12).unwrap_or(text).find(';')?;
+    let c = match &text[1..end] {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{A0}',
+        e => {
+            let number = e.strip_prefix('#')?;
+            let number = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(number)?
+        }
+    };
+    Some((c, end + 1))
+}
+
+/// Value of attribute `name` in the content of a tag
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let mut rest = tag;
+    loop {
+        let pos = rest.find(name)?;
+        let preceded_by_space = rest[..pos].ends_with(char::is_whitespace);
+        rest = &rest[pos + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        if !preceded_by_space {
+            continue;
+        }
+        let value = value.trim_start();
+        let (value, end) = match value.chars().next()? {
+            q @ ('"' | '\'') => {
+                let value = &value[1..];
+                (value, value.find(q).unwrap_or(value.len()))
+            }
+            _ => (
+                value,
+                value.find(char::is_whitespace).unwrap_or(value.len()),
+            ),
+        };
+        return Some(Markup::parse(&value[..end]).strip());
+    }
+}
+
+/// Scrolling text like [`PartiallyAnimatedString`](i3_bar_components::string::PartiallyAnimatedString)
+/// for pango markup. Tags are never cut by the animation.
+/// Text which fits is shown unchanged. Scrolling text only keeps `<b>`, `<i>` and `<u>`.
+pub struct AnimatedMarkup {
+    left_static: Option<String>,
+    source: String,
+    markup: Markup,
+    /// Plain text used to calculate the visible part
+    animated_text: AnimatedString,
+    right_static: Option<String>,
+}
+
+impl AnimatedMarkup {
+    pub fn new(
+        left: Option<String>,
+        source: String,
+        max_width: usize,
+        move_chars_per_sec: usize,
+        right: Option<String>,
+    ) -> Self {
+        let markup = Markup::parse(&source);
+        let animated_text = AnimatedString::new(markup.strip())
+            .with_max_width(max_width)
+            .with_move_chars_per_sec(move_chars_per_sec);
+        Self {
+            left_static: left,
+            source,
+            markup,
+            animated_text,
+            right_static: right,
+        }
+    }
+}
+
+impl ComponentString for AnimatedMarkup {
+    fn to_component_text(&self) -> String {
+        let mut out_text = String::new();
+        if let Some(s) = &self.left_static {
+            out_text.push_str(&escape(s));
+        }
+        let len = self.markup.len();
+        let max_width = self.animated_text.max_width;
+        if len <= max_width {
+            out_text.push_str(&self.source);
+        } else {
+            let start = self.animated_text.start_offset as usize;
+            let visible = self.markup.slice(start, max_width);
+            out_text.push_str(&visible);
+            let padding = max_width.saturating_sub(len.saturating_sub(start));
+            out_text.push_str(&" ".repeat(padding));
+        }
+        if let Some(s) = &self.right_static {
+            out_text.push_str(&escape(s));
+        }
+        out_text
+    }
+
+    fn update(&mut self, dt: f64) {
+        self.animated_text.update(dt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use i3_bar_components::string::ComponentString;
+
+    use super::{AnimatedMarkup, Markup};
+
+    #[test]
+    fn strip_tags_and_entities() {
+        let markup = Markup::parse("<b>Build</b> failed &amp; <i>retried</i> &lt;3 &#x41;&#66;");
+        assert_eq!(markup.strip(), "Build failed & retried <3 AB");
+    }
+
+    #[test]
+    fn unknown_tags_and_invalid_markup() {
+        let markup = Markup::parse("<span>a</span> & b <c");
+        assert_eq!(markup.strip(), "a & b <c");
+    }
+
+    #[test]
+    fn extract_links() {
+        let markup = Markup::parse(
+            r#"See <a href="https://example.com/a?x=1&amp;y=2">the build</a> or <a href='https://example.com/b'>log</a> <img src="x.png" alt="icon"/>"#,
+        );
+        assert_eq!(markup.strip(), "See the build or log icon");
+        assert_eq!(
+            markup.links(),
+            ["https://example.com/a?x=1&y=2", "https://example.com/b"]
+        );
+    }
+
+    #[test]
+    fn to_pango() {
+        let markup = Markup::parse("<b>bold <i>both</i></b> a&amp;b");
+        assert_eq!(markup.to_pango(), "<b>bold </b><b><i>both</i></b> a&amp;b");
+        assert_eq!(markup.slice(2, 5), "<b>ld </b><b><i>bo</i></b>");
+    }
+
+    #[test]
+    fn animated_markup_keeps_text_which_fits() {
+        let text = r#"<span foreground="red">a</span> &amp; <b>b</b>"#;
+        let markup = AnimatedMarkup::new(Some("x".into()), text.into(), 10, 1, None);
+        assert_eq!(markup.to_component_text(), format!("x{text}"));
+
+        let markup = AnimatedMarkup::new(None, text.into(), 3, 1, None);
+        assert_eq!(markup.to_component_text(), "a &amp;");
+    }
+}
@@ -1,21 +1,31 @@
 use std::sync::Arc;
 use std::sync::RwLock;
 
+use crate::control::NotificationInfo;
+use crate::desktop_entry;
+use crate::exec;
+use crate::history::{HistoryEntry, NotificationHistory};
+use crate::history_store::{HistoryStore, StoredNotification};
 use crate::icons;
+use crate::link;
+use crate::markup::{self, Markup, MarkupMode};
+use crate::replay::Recorder;
 use crate::rule::EvalRules;
 use crate::rule::RuleExcutor;
 use emoji::EmojiMode;
 use log::{debug, info};
 use mini_template::macros::ValueContainer;
 use notify_server::notification::Action as NotificationAction;
+use notify_server::notification::Hints;
+use notify_server::notification::NotificationBuilder;
 use notify_server::notification::Urgency;
 use notify_server::CloseReason;
 use notify_server::NotificationId;
 use notify_server::NotifyServer;
 use notify_server::{notification::Notification, Event};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use crate::rule::Style;
+use crate::rule::{ClickBehaviour, ClickBinding, Style};
 
 pub struct NotificationManager<Src = NotifyServer, RE = RuleExcutor>
 where
@@ -25,7 +35,20 @@ where
     notifications: Vec<Arc<RwLock<NotificationData>>>,
     rule_executor: RE,
     default_emoji_mode: EmojiMode,
+    markup_mode: MarkupMode,
     minimum_urgency: Arc<RwLock<MinimalUrgency>>,
+    suppress_mode: Arc<RwLock<SuppressMode>>,
+    /// Notifications below the minimal urgency waiting to be shown
+    queued: Vec<NotificationData>,
+    /// Urgency to restore once do not disturb is turned off again
+    urgency_before_dnd: Option<MinimalUrgency>,
+    history: Arc<RwLock<NotificationHistory>>,
+    history_store: Option<HistoryStore>,
+    history_changed: bool,
+    next_restored_id: u32,
+    /// Notifications closed without a request of the notification server, which still has to be told about it
+    closed: Vec<(NotificationId, CloseReason)>,
+    recorder: Option<Recorder>,
     notify_server: Src,
     commands_rx: std::sync::mpsc::Receiver<NotificationManagerCommand>,
     commands_tx: std::sync::mpsc::Sender<NotificationManagerCommand>,
@@ -53,6 +76,8 @@ where
     pub fn new(
         default_emoji_mode: EmojiMode,
         minimum_urgency: Arc<RwLock<MinimalUrgency>>,
+        suppress_mode: Arc<RwLock<SuppressMode>>,
+        history: Arc<RwLock<NotificationHistory>>,
         notify_server: Src,
         rule_executor: RE,
     ) -> Self {
@@ -62,7 +87,17 @@ where
             notifications: Vec::new(),
             rule_executor,
             default_emoji_mode,
+            markup_mode: MarkupMode::default(),
             minimum_urgency,
+            suppress_mode,
+            queued: Vec::new(),
+            urgency_before_dnd: None,
+            history,
+            history_store: None,
+            history_changed: false,
+            next_restored_id: u32::MAX,
+            closed: Vec::new(),
+            recorder: None,
             notify_server,
             commands_rx: rx,
             commands_tx: tx,
@@ -83,49 +118,103 @@ where
             notification.app_name, notification.summary, notification.body
         );
         debug!("Notification: {:#?}", notification);
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.record(notification);
+        }
+
+        let suppressed = self.is_suppressed(notification.urgency);
+        if suppressed && self.suppress_mode() == SuppressMode::Drop {
+            self.discard(notification.id);
+            return;
+        }
+
+        // Replacements like progress updates do not run the commands again
+        let replaces = self.is_known(notification.id);
+        let mut notification_data = self.evaluate(notification);
+        let commands = std::mem::take(&mut notification_data.commands);
+        if !replaces {
+            for command in commands {
+                exec::spawn(&command, &notification_data);
+            }
+        }
+
+        if notification_data.ignore {
+            self.discard(notification_data.id);
+            return;
+        }
+
+        debug!("Finished definitions");
+        debug!("Final notification_data {:#?}", notification_data);
+
+        if suppressed {
+            self.queue(notification_data);
+        } else {
+            self.show(notification_data);
+        }
+    }
 
+    /// Notification data after applying the rules
+    fn evaluate(&self, notification: &Notification) -> NotificationData {
         let mut notification_data =
-            NotificationData::new(notification, self.default_emoji_mode.clone());
+            NotificationData::new(notification, self.default_emoji_mode.clone())
+                .with_markup_mode(self.markup_mode);
         debug!("Notification Data: {:#?}", notification_data);
 
-        let mut notification_template_data = NotificationTemplateData::from(notification);
+        let mut notification_template_data =
+            NotificationTemplateData::new(notification, self.markup_mode);
         debug!(
             "Notification Tempalate Data: {:#?}",
             notification_template_data
         );
 
-        if *self
-            .minimum_urgency
-            .read()
-            .expect("Could not access urgency")
-            > notification.urgency
-        {
-            return;
-        }
-
         self.rule_executor.eval(
             notification,
             &mut notification_template_data,
             &mut notification_data,
         );
-        drop(notification_template_data);
+        notification_data
+    }
 
-        if notification_data.ignore {
-            return;
+    fn is_suppressed(&self, urgency: Urgency) -> bool {
+        *self
+            .minimum_urgency
+            .read()
+            .expect("Could not access urgency")
+            > urgency
+    }
+
+    fn suppress_mode(&self) -> SuppressMode {
+        *self.suppress_mode.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Tells the notification server a notification is closed again if it is not shown or queued
+    /// under the same id already.
+    fn discard(&mut self, id: NotificationId) {
+        if !self.is_known(id) {
+            self.closed.push((id, CloseReason::Undefined));
         }
+    }
 
-        debug!("Finished definitions");
-        debug!("Final notification_data {:#?}", notification_data);
+    /// Checks if a notification with this id is shown or queued
+    fn is_known(&self, id: NotificationId) -> bool {
+        self.notifications
+            .iter()
+            .any(|n| n.read().unwrap_or_else(|e| e.into_inner()).id == id)
+            || self.queued.iter().any(|n| n.id == id)
+    }
 
+    fn show(&mut self, notification_data: NotificationData) {
         if let Some(mut n) = self
             .notifications
             .iter_mut()
             .filter_map(|n| n.write().ok())
             .find(|n| n.id == notification_data.id)
         {
+            // Replacements like progress updates do not change the stored history
             *n = notification_data;
             return;
         }
+        self.history_changed = true;
         let notification = Arc::new(RwLock::new(notification_data));
         self.notifications.push(Arc::clone(&notification));
         self.events_tx
@@ -133,31 +222,107 @@ where
             .ok();
     }
 
+    fn queue(&mut self, notification_data: NotificationData) {
+        debug!("Queue notification {}", notification_data.id);
+        let len = self.queued.len();
+        self.queued.retain(|n| n.id != notification_data.id);
+        if self.queued.len() == len {
+            self.history_changed = true;
+        }
+        self.queued.push(notification_data);
+        self.events_tx
+            .send(NotificationEvent::Queued(self.queued.len()))
+            .ok();
+    }
+
+    /// Shows all queued notifications the minimal urgency does no longer suppress.
+    /// Notifications which would have expired in the meantime are moved to the history.
+    fn release_queued(&mut self) {
+        if self.queued.is_empty() {
+            return;
+        }
+        let minimum_urgency = *self
+            .minimum_urgency
+            .read()
+            .unwrap_or_else(|e| e.into_inner());
+        let (released, queued) = std::mem::take(&mut self.queued)
+            .into_iter()
+            .partition::<Vec<_>, _>(|n| minimum_urgency <= n.urgency);
+        self.queued = queued;
+        if released.is_empty() {
+            return;
+        }
+
+        let now = chrono::Local::now().timestamp();
+        for n in released {
+            if n.remove_in_secs
+                .is_some_and(|rm| n.time + rm.ceil() as i64 <= now)
+            {
+                self.closed.push((n.id, CloseReason::Expired));
+                if n.hints.transient {
+                    continue;
+                }
+                self.history
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(HistoryEntry::from(&n));
+            } else {
+                self.show(n);
+            }
+        }
+        self.history_changed = true;
+        self.events_tx
+            .send(NotificationEvent::Queued(self.queued.len()))
+            .ok();
+    }
+
     pub async fn update(&mut self, dt: f64) {
-        for cmd in self.commands_rx.try_iter() {
+        while let Ok(cmd) = self.commands_rx.try_recv() {
             match cmd {
                 NotificationManagerCommand::ActionInvoked { id, action } => {
                     self.notify_server.action_invoked(id, &action).await.ok();
+                    // Notifications close after an action is invoked unless they are resident
+                    let close = self
+                        .notifications
+                        .iter()
+                        .map(|n| n.read().unwrap_or_else(|e| e.into_inner()))
+                        .any(|n| n.id == id && !n.hints.resident);
+                    if close {
+                        self.close(id, CloseReason::Dismissed).await;
+                    }
                 }
                 NotificationManagerCommand::CloseNotification { id, reason } => {
-                    self.notify_server
-                        .notification_closed(id, &reason)
-                        .await
-                        .ok();
+                    self.close(id, reason).await;
                 }
                 NotificationManagerCommand::CloseAll { reason } => {
-                    for n in self
+                    // Queued notifications are closed as well
+                    let ids = self
                         .notifications
                         .iter()
-                        .map(|e| e.read().unwrap_or_else(|e| e.into_inner()))
-                        .map(|n| n.id)
-                    {
-                        self.notify_server
-                            .notification_closed(n, &reason)
-                            .await
-                            .ok();
+                        .map(|e| e.read().unwrap_or_else(|e| e.into_inner()).id)
+                        .chain(self.queued.iter().map(|n| n.id))
+                        .collect::<Vec<_>>();
+                    for id in ids {
+                        self.close(id, reason).await;
                     }
                 }
+                NotificationManagerCommand::SetMinimalUrgency(urgency) => {
+                    *self
+                        .minimum_urgency
+                        .write()
+                        .unwrap_or_else(|e| e.into_inner()) = urgency;
+                }
+                NotificationManagerCommand::ToggleDoNotDisturb => self.toggle_do_not_disturb(),
+                NotificationManagerCommand::List(reply) => {
+                    let notifications = self
+                        .notifications
+                        .iter()
+                        .map(|n| {
+                            NotificationInfo::from(&*n.read().unwrap_or_else(|e| e.into_inner()))
+                        })
+                        .collect();
+                    reply.send(notifications).ok();
+                }
             }
         }
         if let Some(events) = self.notify_server.take_events() {
@@ -166,7 +331,7 @@ where
                     Event::Notify(n) => self.notify(&n),
                     Event::Close(id, CloseReason::RequesedByClient) => self
                         .notify_server
-                        .notification_closed(id, &CloseReason::Undefined)
+                        .notification_closed(id, &CloseReason::Closed)
                         .await
                         .unwrap_or(()),
                     Event::Close(id, reason) => self.remove(id, reason),
@@ -174,6 +339,8 @@ where
             }
         }
 
+        self.release_queued();
+
         let mut ids_to_be_removed = Vec::new();
         for n in &self.notifications {
             let Ok(mut n) = n.write() else {
@@ -188,9 +355,42 @@ where
                 ids_to_be_removed.push(n.id);
             }
         }
-        ids_to_be_removed
-            .into_iter()
-            .for_each(|id| self.remove(id, CloseReason::Expired))
+        for id in ids_to_be_removed {
+            self.remove(id, CloseReason::Expired);
+            self.closed.push((id, CloseReason::Expired));
+        }
+
+        for (id, reason) in std::mem::take(&mut self.closed) {
+            if self.is_restored(id) {
+                continue;
+            }
+            self.notify_server
+                .notification_closed(id, &reason)
+                .await
+                .ok();
+        }
+
+        if self.history_changed {
+            self.save_history();
+        }
+    }
+
+    /// Asks the notification server to close a notification. Restored notifications are unknown to the
+    /// server and are removed right away.
+    async fn close(&mut self, id: NotificationId, reason: CloseReason) {
+        if self.is_restored(id) {
+            self.remove(id, reason);
+            return;
+        }
+        self.notify_server
+            .notification_closed(id, &reason)
+            .await
+            .ok();
+    }
+
+    /// Restored notifications get ids counting down from `u32::MAX`
+    fn is_restored(&self, id: NotificationId) -> bool {
+        u32::from(id) > self.next_restored_id
     }
 
     fn remove(&mut self, id: NotificationId, close_reason: CloseReason) {
@@ -209,7 +409,129 @@ where
         });
         if let Some(n) = notification {
             log::debug!("Found notification to close {id}");
+            let n_l = n.read().unwrap_or_else(|e| e.into_inner());
+            // Transient notifications are not kept in the history
+            if !n_l.hints.transient {
+                self.history
+                    .write()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(HistoryEntry::from(&*n_l));
+            }
+            drop(n_l);
+            self.history_changed = true;
             self.events_tx.send(NotificationEvent::Remove(n)).ok();
+        } else if let Some(pos) = self.queued.iter().position(|n| n.id == id) {
+            log::debug!("Found queued notification to close {id}");
+            self.queued.remove(pos);
+            self.history_changed = true;
+            self.events_tx
+                .send(NotificationEvent::Queued(self.queued.len()))
+                .ok();
+        }
+    }
+
+    fn toggle_do_not_disturb(&mut self) {
+        let mut minimum_urgency = self
+            .minimum_urgency
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        if *minimum_urgency == MinimalUrgency::None {
+            *minimum_urgency = self
+                .urgency_before_dnd
+                .take()
+                .unwrap_or(MinimalUrgency::Normal);
+        } else {
+            self.urgency_before_dnd = Some(*minimum_urgency);
+            *minimum_urgency = MinimalUrgency::None;
+        }
+    }
+
+    pub fn set_recorder(&mut self, recorder: Recorder) {
+        self.recorder = Some(recorder);
+    }
+
+    pub fn set_markup_mode(&mut self, markup_mode: MarkupMode) {
+        self.markup_mode = markup_mode;
+    }
+
+    /// Restores all notifications from the store and keeps it updated from now on.
+    pub fn set_history_store(&mut self, store: HistoryStore) {
+        store
+            .load()
+            .into_iter()
+            .for_each(|stored| self.restore(stored));
+        self.history_store = Some(store);
+        self.history_changed = false;
+    }
+
+    fn restore(&mut self, stored: StoredNotification) {
+        let now = chrono::Local::now().timestamp();
+        let remaining = stored.expires_at.map(|expires_at| expires_at - now);
+        if !stored.active || remaining.is_some_and(|r| r <= 0) {
+            self.history
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(stored.into());
+            return;
+        }
+
+        // Ids are counted down from u32::MAX to not collide with ids assigned by the notification server
+        let id = self.next_restored_id.into();
+        self.next_restored_id -= 1;
+        let notification = NotificationBuilder::default()
+            .with_id(id)
+            .with_app_name(stored.app_name)
+            .with_summary(stored.summary)
+            .with_body(stored.body)
+            .with_urgency(stored.urgency.into())
+            .with_expire_timeout(remaining.map_or(-1, |r| r as i32))
+            .build();
+        // Commands already ran when the notification was received
+        let mut notification_data = self.evaluate(&notification);
+        notification_data.commands.clear();
+        notification_data.time = stored.time;
+
+        let suppressed = self.is_suppressed(notification.urgency);
+        if notification_data.ignore || suppressed && self.suppress_mode() == SuppressMode::Drop {
+            self.history
+                .write()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(HistoryEntry::from(&notification_data));
+        } else if suppressed {
+            self.queue(notification_data);
+        } else {
+            self.show(notification_data);
+        }
+    }
+
+    fn save_history(&mut self) {
+        self.history_changed = false;
+        let Some(store) = &self.history_store else {
+            return;
+        };
+        let now = chrono::Local::now().timestamp();
+        let history = self.history.read().unwrap_or_else(|e| e.into_inner());
+        let mut stored = history
+            .iter()
+            .map(StoredNotification::from)
+            .collect::<Vec<_>>();
+        // History is iterated newest first but stored oldest first
+        stored.reverse();
+        stored.extend(
+            self.notifications
+                .iter()
+                .map(|n| n.read().unwrap_or_else(|e| e.into_inner()))
+                .filter(|n| !n.hints.transient)
+                .map(|n| StoredNotification::from_active(&n, now)),
+        );
+        stored.extend(
+            self.queued
+                .iter()
+                .filter(|n| !n.hints.transient)
+                .map(|n| StoredNotification::from_active(n, now)),
+        );
+        if let Err(e) = store.save(&stored) {
+            log::error!("Could not save notification history: {e}");
         }
     }
 
@@ -295,6 +617,29 @@ impl CloseAllNotifications for NotificationManagerCommands {
     }
 }
 
+impl NotificationManagerCommands {
+    pub fn set_minimal_urgency(&self, urgency: MinimalUrgency) {
+        self.commands
+            .send(NotificationManagerCommand::SetMinimalUrgency(urgency))
+            .ok();
+    }
+
+    pub fn toggle_do_not_disturb(&self) {
+        self.commands
+            .send(NotificationManagerCommand::ToggleDoNotDisturb)
+            .ok();
+    }
+
+    /// Requests a list of all active notifications. The list is sent on the next update.
+    pub fn list_notifications(&self) -> std::sync::mpsc::Receiver<Vec<NotificationInfo>> {
+        let (tx, rx) = std::sync::mpsc::channel();
+        self.commands
+            .send(NotificationManagerCommand::List(tx))
+            .ok();
+        rx
+    }
+}
+
 impl Clone for NotificationManagerCommands {
     fn clone(&self) -> Self {
         Self {
@@ -315,12 +660,17 @@ enum NotificationManagerCommand {
     CloseAll {
         reason: CloseReason,
     },
+    SetMinimalUrgency(MinimalUrgency),
+    ToggleDoNotDisturb,
+    List(std::sync::mpsc::Sender<Vec<NotificationInfo>>),
 }
 
 #[derive(Debug)]
 pub enum NotificationEvent {
     Remove(Arc<RwLock<NotificationData>>),
     Add(Arc<RwLock<NotificationData>>),
+    /// Number of notifications currently queued
+    Queued(usize),
 }
 
 #[derive(Debug)]
@@ -336,12 +686,39 @@ pub struct NotificationData {
     pub ignore: bool,
     pub actions: Vec<NotificationAction>,
     pub group: Option<String>,
+    /// Progress in percent from the `value` hint
+    pub value: Option<u8>,
+    pub app_name: String,
+    /// Id of the matching desktop entry, see [`desktop_entry::app_id`]
+    pub app_id: String,
+    pub summary: String,
+    pub body: String,
+    pub urgency: Urgency,
+    /// Time the notification was received as unix timestamp
+    pub time: i64,
+    /// Commands of matched exec actions. Started by the notification manager after all rules are evaluated.
+    pub commands: Vec<Vec<String>>,
+    pub app_icon: String,
+    pub hints: Hints,
+    /// Click bindings of matched rules. Later bindings take precedence.
+    pub on_click: Vec<ClickBinding>,
+    /// Targets of links in the body
+    pub links: Vec<String>,
+    /// If set to pango `text` contains pango markup
+    pub markup_mode: MarkupMode,
+    /// Unique bus name of the sending application
+    pub sender: Option<String>,
 }
 
 impl NotificationData {
     pub fn new(notification: &Notification, emoji_mode: EmojiMode) -> Self {
         use std::sync::atomic;
         static NOTIFY_EVENT_ID: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+        let body = Markup::parse(&notification.body);
+        let app_id = desktop_entry::app_id(
+            &notification.app_name,
+            notification.hints.desktop_entry.as_deref(),
+        );
         Self {
             expire_timeout: notification.expire_timeout,
             remove_in_secs: if notification.expire_timeout < 0 {
@@ -349,7 +726,9 @@ impl NotificationData {
             } else {
                 Some(notification.expire_timeout as f64)
             },
-            icon: icons::get_icon(&notification.app_name).unwrap_or(' '),
+            icon: icons::get_icon(&notification.app_name)
+                .or_else(|| icons::get_icon(&app_id))
+                .unwrap_or(' '),
             id: notification.id,
             notification_update_id: NOTIFY_EVENT_ID.fetch_add(1, atomic::Ordering::Relaxed),
             style: Vec::new(),
@@ -358,13 +737,71 @@ impl NotificationData {
             ignore: false,
             actions: notification.actions.clone(),
             group: None,
+            value: notification.hints.value.map(|v| v.clamp(0, 100) as u8),
+            app_name: notification.app_name.clone(),
+            app_id,
+            summary: notification.summary.clone(),
+            body: body.strip(),
+            urgency: notification.urgency,
+            time: chrono::Local::now().timestamp(),
+            commands: Vec::new(),
+            app_icon: notification.app_icon.clone(),
+            hints: notification.hints.clone(),
+            on_click: Vec::new(),
+            links: links(&body),
+            markup_mode: MarkupMode::Strip,
+            sender: notification.sender.clone(),
+        }
+    }
+
+    /// The default text is escaped if `markup_mode` is pango
+    pub fn with_markup_mode(mut self, markup_mode: MarkupMode) -> Self {
+        if markup_mode == MarkupMode::Pango && self.markup_mode != MarkupMode::Pango {
+            self.text = markup::escape(&self.text);
+        }
+        self.markup_mode = markup_mode;
+        self
+    }
+
+    /// `text` without markup
+    pub fn plain_text(&self) -> String {
+        match self.markup_mode {
+            MarkupMode::Strip => self.text.clone(),
+            MarkupMode::Pango => Markup::parse(&self.text).strip(),
+        }
+    }
+
+    /// Behaviour bound to `button` with exactly `modifiers` held
+    pub fn click_behaviour(&self, button: usize, modifiers: &[String]) -> Option<&ClickBehaviour> {
+        self.on_click
+            .iter()
+            .rev()
+            .find(|b| b.matches(button, modifiers))
+            .map(|b| &b.behaviour)
+    }
+}
+
+/// Targets of `<a>` tags followed by bare URLs in the text. Targets with other schemes than
+/// http, https and mailto are left out.
+fn links(body: &Markup) -> Vec<String> {
+    let mut links = body
+        .links()
+        .iter()
+        .filter(|url| link::is_allowed(url))
+        .cloned()
+        .collect::<Vec<_>>();
+    for url in link::find_urls(&body.strip()) {
+        if !links.contains(&url) {
+            links.push(url);
         }
     }
+    links
 }
 
 #[derive(Debug, Serialize, ValueContainer, Clone)]
 pub struct NotificationTemplateData {
     pub app_name: String,
+    pub app_id: String,
     pub icon: String,
     pub summary: String,
     pub body: String,
@@ -372,27 +809,70 @@ pub struct NotificationTemplateData {
     pub time: i64,
 }
 
-impl From<&Notification> for NotificationTemplateData {
-    fn from(notification: &Notification) -> Self {
+impl NotificationTemplateData {
+    /// With pango all values are escaped and markup in the body is converted to pango.
+    /// Commands are rendered with the unescaped values of [`NotificationTemplateData::from`].
+    pub fn new(notification: &Notification, markup_mode: MarkupMode) -> Self {
+        let body = Markup::parse(&notification.body);
+        let (escape, body): (fn(&str) -> String, _) = match markup_mode {
+            MarkupMode::Strip => (str::to_owned, body.strip()),
+            MarkupMode::Pango => (markup::escape, body.to_pango()),
+        };
         Self {
-            app_name: notification.app_name.clone(),
-            icon: notification.app_icon.clone(),
-            summary: notification.summary.clone(),
-            body: notification.body.clone(),
+            app_name: escape(&notification.app_name),
+            app_id: escape(&desktop_entry::app_id(
+                &notification.app_name,
+                notification.hints.desktop_entry.as_deref(),
+            )),
+            icon: escape(&notification.app_icon),
+            summary: escape(&notification.summary),
+            body,
             expire_timeout: notification.expire_timeout,
             time: chrono::Local::now().timestamp(),
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl From<&Notification> for NotificationTemplateData {
+    fn from(notification: &Notification) -> Self {
+        Self::new(notification, MarkupMode::Strip)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum MinimalUrgency {
+    #[serde(rename = "low")]
     All = 0,
     Normal = 1,
     Critical = 2,
+    #[serde(rename = "off")]
     None = 3,
 }
 
+/// What happens to notifications below the minimal urgency
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SuppressMode {
+    /// Keep them until the minimal urgency is lowered
+    Queue,
+    #[default]
+    Drop,
+}
+
+impl std::str::FromStr for MinimalUrgency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "low" => MinimalUrgency::All,
+            "normal" => MinimalUrgency::Normal,
+            "critical" => MinimalUrgency::Critical,
+            "off" => MinimalUrgency::None,
+            _ => return Err(format!("Can not convert {} to minimal urgency", s)),
+        })
+    }
+}
+
 impl std::cmp::PartialEq<Urgency> for MinimalUrgency {
     fn eq(&self, other: &Urgency) -> bool {
         *self as usize == *other as usize
@@ -435,7 +915,12 @@ mod tests {
         rule::{EvalRules, RuleExcutor},
     };
 
-    use super::{MinimalUrgency, NotificationData, NotificationManager};
+    use super::{
+        MinimalUrgency, NotificationData, NotificationEvent, NotificationManager, SuppressMode,
+    };
+    use crate::{
+        history::NotificationHistory, history_store::StoredNotification, markup::MarkupMode,
+    };
 
     fn minimal_notification_manager<RE: EvalRules + Send + Sync + 'static>(
         notify_src: notify_server::MockNotificationSource,
@@ -444,6 +929,8 @@ mod tests {
         NotificationManager::new(
             emoji::EmojiMode::Ignore,
             Arc::new(RwLock::new(MinimalUrgency::Normal)),
+            Arc::new(RwLock::new(SuppressMode::Drop)),
+            Arc::new(RwLock::new(NotificationHistory::new(10))),
             notify_src,
             rule_evaluator,
         )
@@ -462,6 +949,20 @@ mod tests {
             remove_in_secs: None,
             style: Default::default(),
             text: Default::default(),
+            value: None,
+            app_name: Default::default(),
+            app_id: Default::default(),
+            summary: Default::default(),
+            body: Default::default(),
+            urgency: Default::default(),
+            time: 0,
+            commands: Vec::new(),
+            app_icon: Default::default(),
+            hints: Default::default(),
+            on_click: Vec::new(),
+            links: Vec::new(),
+            markup_mode: Default::default(),
+            sender: None,
         }
     }
 
@@ -475,6 +976,8 @@ mod tests {
             urgency: Urgency::Normal,
             actions: vec![],
             expire_timeout: -1,
+            hints: Default::default(),
+            sender: None,
         }
     }
 
@@ -496,6 +999,21 @@ mod tests {
         );
     }
 
+    #[test]
+    fn notification_data_links() {
+        let mut notification = server_notification();
+        notification.body = r#"<a href="https://example.com/pr/1">PR</a> https://example.com/pr/1 and https://example.com/ci. <a href="file:///etc/passwd">log</a>"#.into();
+        let nd = NotificationData::new(&notification, emoji::EmojiMode::Ignore);
+        assert_eq!(
+            nd.body,
+            "PR https://example.com/pr/1 and https://example.com/ci. log"
+        );
+        assert_eq!(
+            nd.links,
+            ["https://example.com/pr/1", "https://example.com/ci"]
+        );
+    }
+
     #[test]
     fn notification_manager_notify() {
         let notify_src = notify_server::MockNotificationSource::default();
@@ -512,6 +1030,19 @@ mod tests {
         assert_eq!(nm.notifications.len(), 2);
     }
 
+    #[test]
+    fn notification_manager_notify_replaces_value() {
+        let notify_src = notify_server::MockNotificationSource::default();
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let mut notification = server_notification();
+        notification.hints.value = Some(20);
+        nm.notify(&notification);
+        notification.hints.value = Some(150);
+        nm.notify(&notification);
+        assert_eq!(nm.notifications.len(), 1);
+        assert_eq!(nm.notifications[0].read().unwrap().value, Some(100));
+    }
+
     #[test]
     fn notification_manager_notify_urgency_check() {
         let notify_src = notify_server::MockNotificationSource::default();
@@ -548,6 +1079,84 @@ mod tests {
         nm.update(0.0).await;
     }
 
+    #[async_std::test]
+    async fn notification_manager_action_closes_notification() {
+        use mockall::predicate::eq;
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src
+            .expect_action_invoked()
+            .times(2)
+            .returning(|_, _| Ok(()));
+        notify_src
+            .expect_notification_closed()
+            .once()
+            .with(
+                eq::<notify_server::NotificationId>(1.into()),
+                eq(&CloseReason::Dismissed),
+            )
+            .returning(|_, _| Ok(()));
+        notify_src.expect_take_events().once().returning(|| None);
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let mut resident = notification(2);
+        resident.hints.resident = true;
+        nm.notifications
+            .push(Arc::new(RwLock::new(notification(1))));
+        nm.notifications.push(Arc::new(RwLock::new(resident)));
+        nm.action_invoked(1.into(), "default");
+        nm.action_invoked(2.into(), "default");
+        nm.update(0.0).await;
+    }
+
+    #[async_std::test]
+    async fn notification_manager_close_requested_by_client() {
+        use mockall::predicate::eq;
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().once().returning(|| {
+            Some(vec![notify_server::Event::Close(
+                4.into(),
+                CloseReason::RequesedByClient,
+            )])
+        });
+        notify_src
+            .expect_notification_closed()
+            .once()
+            .with(
+                eq::<notify_server::NotificationId>(4.into()),
+                eq(&CloseReason::Closed),
+            )
+            .returning(|_, _| Ok(()));
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        nm.update(0.0).await;
+    }
+
+    #[async_std::test]
+    async fn notification_manager_transient_skips_history() {
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().once().returning(|| {
+            Some(vec![
+                notify_server::Event::Close(5.into(), CloseReason::Dismissed),
+                notify_server::Event::Close(6.into(), CloseReason::Dismissed),
+            ])
+        });
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let mut transient = notification(5);
+        transient.hints.transient = true;
+        nm.notifications.push(Arc::new(RwLock::new(transient)));
+        nm.notifications
+            .push(Arc::new(RwLock::new(notification(6))));
+        nm.update(0.0).await;
+        assert!(nm.notifications.is_empty());
+        assert_eq!(
+            nm.history
+                .read()
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>(),
+            vec![6.into()]
+        );
+    }
+
     #[async_std::test]
     async fn notification_manager_notification_closed() {
         use mockall::predicate::eq;
@@ -597,4 +1206,429 @@ mod tests {
         nm.update(0.0).await;
         assert!(nm.notifications.is_empty());
     }
+
+    #[async_std::test]
+    async fn notification_manager_close_all_clears_queue() {
+        use mockall::predicate::{eq, in_iter};
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src
+            .expect_notification_closed()
+            .times(2)
+            .with(
+                in_iter::<_, notify_server::NotificationId>(vec![1.into(), 2.into()]),
+                eq(&CloseReason::Dismissed),
+            )
+            .returning(|_, _| Ok(()));
+        notify_src.expect_take_events().once().returning(|| None);
+        // The notification server reports them as closed afterwards
+        notify_src.expect_take_events().once().returning(|| {
+            use notify_server::Event::Close;
+            Some(vec![
+                Close(1.into(), CloseReason::Dismissed),
+                Close(2.into(), CloseReason::Dismissed),
+            ])
+        });
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        nm.show(notification(1));
+        nm.queue(notification(2));
+
+        nm.close_all_notifications(CloseReason::Dismissed);
+        nm.update(0.0).await;
+        nm.update(0.0).await;
+        assert!(nm.notifications.is_empty());
+        assert!(nm.queued.is_empty());
+    }
+
+    #[async_std::test]
+    async fn notification_manager_remove_adds_history_entry() {
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().once().returning(|| {
+            Some(vec![notify_server::Event::Close(
+                7.into(),
+                CloseReason::Dismissed,
+            )])
+        });
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let mut nd = notification(7);
+        nd.summary = "summary".into();
+        nm.notifications.push(Arc::new(RwLock::new(nd)));
+        nm.update(0.0).await;
+        assert!(nm.notifications.is_empty());
+        let history = nm.history.read().unwrap();
+        assert_eq!(history.iter().count(), 1);
+        let entry = history.iter().next().unwrap();
+        assert_eq!(entry.id, 7.into());
+        assert_eq!(entry.summary, "summary");
+    }
+
+    #[test]
+    fn notification_manager_restore() {
+        let notify_src = notify_server::MockNotificationSource::default();
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let now = chrono::Local::now().timestamp();
+        let stored = |active, summary: &str, expires_at| StoredNotification {
+            active,
+            app_name: "app".into(),
+            summary: summary.into(),
+            body: "".into(),
+            urgency: 1,
+            time: 42,
+            group: None,
+            icon: ' ',
+            text: summary.into(),
+            expires_at,
+        };
+        nm.restore(stored(false, "history", None));
+        nm.restore(stored(true, "expired", Some(now - 10)));
+        nm.restore(stored(true, "active", Some(now + 100)));
+        nm.restore(stored(true, "never expires", None));
+
+        let history = nm.history.read().unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|e| e.summary.as_str())
+                .collect::<Vec<_>>(),
+            vec!["expired", "history"]
+        );
+        assert_eq!(nm.notifications.len(), 2);
+        let active = nm.notifications[0].read().unwrap();
+        assert_eq!(active.summary, "active");
+        assert_eq!(active.time, 42);
+        assert_eq!(active.id, u32::MAX.into());
+        assert!(active.remove_in_secs.is_some());
+        let never_expires = nm.notifications[1].read().unwrap();
+        assert_eq!(never_expires.id, (u32::MAX - 1).into());
+        assert!(never_expires.remove_in_secs.is_none());
+    }
+
+    #[test]
+    fn notification_manager_restore_without_commands() {
+        struct Rules;
+        impl EvalRules for Rules {
+            fn eval(
+                &self,
+                n: &Notification,
+                _: &mut super::NotificationTemplateData,
+                nd: &mut NotificationData,
+            ) {
+                nd.ignore = n.summary == "ignored";
+                nd.commands.push(vec!["true".into()]);
+            }
+        }
+        let notify_src = notify_server::MockNotificationSource::default();
+        let mut nm = minimal_notification_manager(notify_src, Rules);
+        nm.set_markup_mode(MarkupMode::Pango);
+        let stored = |summary: &str, urgency| StoredNotification {
+            active: true,
+            app_name: "app".into(),
+            summary: summary.into(),
+            body: "".into(),
+            urgency,
+            time: 42,
+            group: None,
+            icon: ' ',
+            text: summary.into(),
+            expires_at: None,
+        };
+        nm.restore(stored("ignored", 1));
+        nm.restore(stored("suppressed", 0));
+        nm.restore(stored("shown", 1));
+
+        let history = nm.history.read().unwrap();
+        assert_eq!(
+            history
+                .iter()
+                .map(|e| (e.summary.as_str(), e.time))
+                .collect::<Vec<_>>(),
+            vec![("suppressed", 42), ("ignored", 42)]
+        );
+        assert_eq!(nm.notifications.len(), 1);
+        let shown = nm.notifications[0].read().unwrap();
+        assert_eq!(shown.summary, "shown");
+        assert_eq!(shown.markup_mode, MarkupMode::Pango);
+        assert!(shown.commands.is_empty());
+    }
+
+    #[async_std::test]
+    async fn notification_manager_close_restored_without_signal() {
+        use mockall::predicate::eq;
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().times(2).returning(|| None);
+        // Only the notification of the server is signalled
+        notify_src
+            .expect_notification_closed()
+            .once()
+            .with(
+                eq::<notify_server::NotificationId>(1.into()),
+                eq(&CloseReason::Dismissed),
+            )
+            .returning(|_, _| Ok(()));
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let restored = || StoredNotification {
+            active: true,
+            app_name: "app".into(),
+            summary: "restored".into(),
+            body: "".into(),
+            urgency: 1,
+            time: 42,
+            group: None,
+            icon: ' ',
+            text: "restored".into(),
+            expires_at: Some(chrono::Local::now().timestamp() + 1),
+        };
+        nm.restore(restored());
+        nm.show(notification(1));
+
+        nm.notification_closed(u32::MAX.into(), CloseReason::Dismissed);
+        nm.notification_closed(1.into(), CloseReason::Dismissed);
+        nm.update(0.0).await;
+        assert_eq!(nm.notifications.len(), 1);
+        assert_eq!(nm.history.read().unwrap().iter().count(), 1);
+        // Expiring restored notifications are not signalled either
+        nm.restore(restored());
+        nm.update(5.0).await;
+        assert_eq!(nm.notifications.len(), 1);
+    }
+
+    #[test]
+    fn notification_manager_replace_does_not_run_commands() {
+        struct Exec(std::path::PathBuf);
+        impl EvalRules for Exec {
+            fn eval(
+                &self,
+                _: &Notification,
+                _: &mut super::NotificationTemplateData,
+                nd: &mut NotificationData,
+            ) {
+                nd.commands.push(vec![
+                    "sh".into(),
+                    "-c".into(),
+                    format!("echo run >> {}", self.0.display()),
+                ]);
+            }
+        }
+        let path =
+            std::env::temp_dir().join(format!("i3_notify_bar_exec_test_{}", std::process::id()));
+        let notify_src = notify_server::MockNotificationSource::default();
+        let mut nm = minimal_notification_manager(notify_src, Exec(path.clone()));
+        nm.notify(&server_notification());
+        nm.notify(&server_notification());
+
+        let runs = || std::fs::read_to_string(&path).map_or(0, |s| s.lines().count());
+        for _ in 0..50 {
+            if runs() > 0 {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(100));
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+        assert_eq!(runs(), 1);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn notification_manager_replace_keeps_history() {
+        let notify_src = notify_server::MockNotificationSource::default();
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        nm.show(notification(1));
+        assert!(nm.history_changed);
+        nm.history_changed = false;
+        let mut progress = notification(1);
+        progress.value = Some(50);
+        nm.show(progress);
+        assert!(!nm.history_changed);
+        assert_eq!(nm.notifications[0].read().unwrap().value, Some(50));
+    }
+
+    #[async_std::test]
+    async fn notification_manager_toggle_do_not_disturb() {
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().times(3).returning(|| None);
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let commands = nm.linked_commands();
+
+        commands.set_minimal_urgency(MinimalUrgency::Critical);
+        nm.update(0.0).await;
+        assert_eq!(
+            *nm.minimum_urgency.read().unwrap(),
+            MinimalUrgency::Critical
+        );
+
+        commands.toggle_do_not_disturb();
+        nm.update(0.0).await;
+        assert_eq!(*nm.minimum_urgency.read().unwrap(), MinimalUrgency::None);
+
+        commands.toggle_do_not_disturb();
+        nm.update(0.0).await;
+        assert_eq!(
+            *nm.minimum_urgency.read().unwrap(),
+            MinimalUrgency::Critical
+        );
+    }
+
+    #[async_std::test]
+    async fn notification_manager_list_notifications() {
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().once().returning(|| None);
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let mut n = notification(3);
+        n.summary = "summary".into();
+        nm.notifications.push(Arc::new(RwLock::new(n)));
+
+        let list = nm.linked_commands().list_notifications();
+        nm.update(0.0).await;
+        let list = list.try_recv().unwrap();
+        assert_eq!(list.len(), 1);
+        assert_eq!(list[0].id, 3);
+        assert_eq!(list[0].summary, "summary");
+    }
+
+    #[async_std::test]
+    async fn notification_manager_queue_suppressed() {
+        use mockall::predicate::eq;
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().times(2).returning(|| None);
+        notify_src
+            .expect_notification_closed()
+            .once()
+            .with(
+                eq::<notify_server::NotificationId>(1.into()),
+                eq(&CloseReason::Expired),
+            )
+            .returning(|_, _| Ok(()));
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let events = nm.event_channel();
+        *nm.suppress_mode.write().unwrap() = SuppressMode::Queue;
+        *nm.minimum_urgency.write().unwrap() = MinimalUrgency::Critical;
+
+        let mut notification = server_notification();
+        nm.notify(&notification);
+        notification.id = 1.into();
+        notification.expire_timeout = 1;
+        nm.notify(&notification);
+        assert_eq!(nm.notifications.len(), 0);
+        assert_eq!(nm.queued.len(), 2);
+        assert!(matches!(
+            events.try_recv(),
+            Ok(NotificationEvent::Queued(1))
+        ));
+        assert!(matches!(
+            events.try_recv(),
+            Ok(NotificationEvent::Queued(2))
+        ));
+
+        nm.update(0.0).await;
+        assert_eq!(nm.queued.len(), 2);
+
+        // Pretend the second notification arrived long ago so it would have expired already
+        nm.queued[1].time -= 10;
+        *nm.minimum_urgency.write().unwrap() = MinimalUrgency::Normal;
+        nm.update(0.0).await;
+        assert_eq!(nm.queued.len(), 0);
+        assert_eq!(nm.notifications.len(), 1);
+        assert_eq!(nm.notifications[0].read().unwrap().id, 0.into());
+        assert_eq!(
+            nm.history
+                .read()
+                .unwrap()
+                .iter()
+                .map(|e| e.id)
+                .collect::<Vec<_>>(),
+            vec![1.into()]
+        );
+    }
+
+    #[async_std::test]
+    async fn notification_manager_expired_notification_is_closed() {
+        use mockall::predicate::eq;
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().times(2).returning(|| None);
+        notify_src
+            .expect_notification_closed()
+            .once()
+            .with(
+                eq::<notify_server::NotificationId>(3.into()),
+                eq(&CloseReason::Expired),
+            )
+            .returning(|_, _| Ok(()));
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        let mut n = notification(3);
+        n.remove_in_secs = Some(1.);
+        nm.show(n);
+
+        nm.update(0.5).await;
+        assert_eq!(nm.notifications.len(), 1);
+        nm.update(0.5).await;
+        assert_eq!(nm.notifications.len(), 0);
+    }
+
+    #[async_std::test]
+    async fn notification_manager_dropped_notification_is_closed() {
+        use mockall::predicate::eq;
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().once().returning(|| None);
+        notify_src
+            .expect_notification_closed()
+            .once()
+            .with(
+                eq::<notify_server::NotificationId>(4.into()),
+                eq(&CloseReason::Undefined),
+            )
+            .returning(|_, _| Ok(()));
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        *nm.minimum_urgency.write().unwrap() = MinimalUrgency::Critical;
+
+        // Replacing a shown notification keeps its id active
+        nm.show(notification(5));
+        let mut n = server_notification();
+        n.id = 5.into();
+        nm.notify(&n);
+        n.id = 4.into();
+        nm.notify(&n);
+        assert_eq!(nm.queued.len(), 0);
+        nm.update(0.0).await;
+    }
+
+    #[async_std::test]
+    async fn notification_manager_ignored_notification_is_closed() {
+        use mockall::predicate::eq;
+        struct Ignore;
+        impl EvalRules for Ignore {
+            fn eval(
+                &self,
+                _: &Notification,
+                _: &mut super::NotificationTemplateData,
+                nd: &mut NotificationData,
+            ) {
+                nd.ignore = true;
+            }
+        }
+        let mut notify_src = notify_server::MockNotificationSource::default();
+        notify_src.expect_take_events().once().returning(|| None);
+        notify_src
+            .expect_notification_closed()
+            .once()
+            .with(
+                eq::<notify_server::NotificationId>(0.into()),
+                eq(&CloseReason::Undefined),
+            )
+            .returning(|_, _| Ok(()));
+        let mut nm = minimal_notification_manager(notify_src, Ignore);
+        nm.notify(&server_notification());
+        assert_eq!(nm.notifications.len(), 0);
+        nm.update(0.0).await;
+    }
+
+    #[test]
+    fn notification_manager_remove_queued() {
+        let notify_src = notify_server::MockNotificationSource::default();
+        let mut nm = minimal_notification_manager(notify_src, RuleExcutor::new(vec![]));
+        *nm.suppress_mode.write().unwrap() = SuppressMode::Queue;
+        *nm.minimum_urgency.write().unwrap() = MinimalUrgency::Critical;
+        nm.notify(&server_notification());
+        assert_eq!(nm.queued.len(), 1);
+        nm.remove(0.into(), CloseReason::Closed);
+        assert_eq!(nm.queued.len(), 0);
+    }
 }
@@ -0,0 +1,118 @@
+use std::{path::Path, sync::mpsc::Receiver};
+
+use i3_bar_components::{
+    component_manager::ComponentManager,
+    output::{I3barOutput, JsonLinesOutput, Output, PolybarOutput},
+    protocol::ClickEvent,
+};
+
+/// Shows the notifications. Updated once per refresh.
+pub trait Bar {
+    fn update(&mut self);
+}
+
+impl Bar for ComponentManager {
+    fn update(&mut self) {
+        ComponentManager::update(self)
+    }
+}
+
+/// What is written to stdout
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputMode {
+    /// Notification components written in one of the formats
+    Components(ComponentOutput),
+    /// Summary of all notifications for a waybar custom module, see [`crate::waybar::Waybar`]
+    Waybar,
+}
+
+impl Default for OutputMode {
+    fn default() -> Self {
+        Self::Components(ComponentOutput::I3bar)
+    }
+}
+
+/// Format the components are written in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ComponentOutput {
+    /// i3bar protocol with click events
+    I3bar,
+    /// One JSON object per update for custom modules of other bars
+    JsonLines,
+    /// Single line with formatting tags for polybar or lemonbar
+    Polybar,
+}
+
+impl std::str::FromStr for OutputMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "i3bar" => OutputMode::Components(ComponentOutput::I3bar),
+            "json-lines" => OutputMode::Components(ComponentOutput::JsonLines),
+            "waybar" => OutputMode::Waybar,
+            "polybar" => OutputMode::Components(ComponentOutput::Polybar),
+            _ => return Err(format!("Can not convert {} to output mode", s)),
+        })
+    }
+}
+
+impl ComponentOutput {
+    /// Bars without click events on stdin run `click_command`. The clicks are expected on `clicks`,
+    /// which is dropped by outputs without support for it.
+    pub fn create(
+        self,
+        click_command: &str,
+        clicks: Receiver<ClickEvent>,
+    ) -> std::io::Result<Box<dyn Output>> {
+        Ok(match self {
+            ComponentOutput::I3bar => Box::new(I3barOutput::new(std::io::stdout(), true)?),
+            ComponentOutput::JsonLines => Box::new(JsonLinesOutput::new(std::io::stdout())),
+            ComponentOutput::Polybar => {
+                Box::new(PolybarOutput::new(std::io::stdout(), click_command, clicks))
+            }
+        })
+    }
+}
+
+/// Shell command reporting a click to the instance listening on `socket_file`
+pub fn click_command(exe: &Path, socket_file: Option<&Path>) -> String {
+    let socket_file = socket_file
+        .map(|file| {
+            format!(
+                " --socket-file {}",
+                shell_quote(&file.display().to_string())
+            )
+        })
+        .unwrap_or_default();
+    format!(
+        "{}{} ctl click",
+        shell_quote(&exe.display().to_string()),
+        socket_file
+    )
+}
+
+/// Quotes `arg` for sh. Single quotes inside are closed, escaped and reopened.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    #[test]
+    fn click_command() {
+        assert_eq!(
+            super::click_command(Path::new("/usr/bin/i3_notify_bar"), None),
+            "'/usr/bin/i3_notify_bar' ctl click"
+        );
+        assert_eq!(
+            super::click_command(
+                Path::new("/home/o'neil/bin/i3_notify_bar"),
+                Some(Path::new("/run/user/1000/bar.sock"))
+            ),
+            r"'/home/o'\''neil/bin/i3_notify_bar' --socket-file '/run/user/1000/bar.sock' ctl click"
+        );
+    }
+}
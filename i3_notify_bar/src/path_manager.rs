@@ -15,10 +15,17 @@ fn home_dir() -> Option<String> {
     None
 }
 
+fn runtime_dir() -> Option<PathBuf> {
+    env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from)
+}
+
 pub struct PathManager {
     log_file: Option<PathBuf>,
     config_file: Option<PathBuf>,
     emoji_file: Option<PathBuf>,
+    icon_file: Option<PathBuf>,
+    history_file: Option<PathBuf>,
+    socket_file: Option<PathBuf>,
 }
 
 impl Default for PathManager {
@@ -30,6 +37,9 @@ impl Default for PathManager {
                     config_file: None,
                     log_file: None,
                     emoji_file: None,
+                    icon_file: None,
+                    history_file: None,
+                    socket_file: runtime_dir().map(|dir| dir.join("i3_notify_bar.sock")),
                 }
             }
         };
@@ -37,11 +47,20 @@ impl Default for PathManager {
         let log_file = home_dir.join(".config/i3_notify_bar/log");
         let config_file = home_dir.join(".config/i3_notify_bar/config");
         let emoji_file = home_dir.join(".config/i3_notify_bar/emojis");
+        let icon_file = home_dir.join(".config/i3_notify_bar/icons");
+        let history_file = home_dir.join(".local/state/i3_notify_bar/history.jsonl");
+        let socket_file = runtime_dir().map_or_else(
+            || home_dir.join(".local/state/i3_notify_bar/control.sock"),
+            |dir| dir.join("i3_notify_bar.sock"),
+        );
 
         PathManager {
             log_file: Some(log_file),
             config_file: Some(config_file),
             emoji_file: Some(emoji_file),
+            icon_file: Some(icon_file),
+            history_file: Some(history_file),
+            socket_file: Some(socket_file),
         }
     }
 }
@@ -70,4 +89,28 @@ impl PathManager {
     pub fn emoji_file(&self) -> Option<&Path> {
         self.emoji_file.as_deref()
     }
+
+    pub fn set_icon_file(&mut self, file: PathBuf) {
+        self.icon_file = Some(file)
+    }
+
+    pub fn icon_file(&self) -> Option<&Path> {
+        self.icon_file.as_deref()
+    }
+
+    pub fn set_history_file(&mut self, file: PathBuf) {
+        self.history_file = Some(file)
+    }
+
+    pub fn history_file(&self) -> Option<&Path> {
+        self.history_file.as_deref()
+    }
+
+    pub fn set_socket_file(&mut self, file: PathBuf) {
+        self.socket_file = Some(file)
+    }
+
+    pub fn socket_file(&self) -> Option<&Path> {
+        self.socket_file.as_deref()
+    }
 }
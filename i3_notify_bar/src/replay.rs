@@ -0,0 +1,154 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufWriter, Write},
+    path::Path,
+};
+
+use emoji::EmojiMode;
+use log::{error, warn};
+use notify_server::notification::Notification;
+use serde::Serialize;
+
+use crate::{
+    notification_bar::{NotificationData, NotificationTemplateData},
+    rule::{EvalRules, Style},
+};
+
+/// Appends every received notification as a JSON line so it can be replayed later.
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+        })
+    }
+
+    pub fn record(&mut self, notification: &Notification) {
+        let result = serde_json::to_writer(&mut self.writer, notification)
+            .map_err(std::io::Error::from)
+            .and_then(|_| self.writer.write_all(b"\n"))
+            .and_then(|_| self.writer.flush());
+        if let Err(e) = result {
+            error!("Could not record notification: {e}");
+        }
+    }
+}
+
+/// Outcome of the rules for a single replayed notification
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ReplayResult {
+    pub id: u32,
+    pub text: String,
+    pub icon: char,
+    pub style: Vec<Style>,
+    pub group: Option<String>,
+    pub expire_timeout: i32,
+    pub ignore: bool,
+    /// Commands of exec actions. They are not run during a replay.
+    pub commands: Vec<Vec<String>>,
+}
+
+impl From<&NotificationData> for ReplayResult {
+    fn from(nd: &NotificationData) -> Self {
+        Self {
+            id: nd.id.into(),
+            text: nd.text.clone(),
+            icon: nd.icon,
+            style: nd.style.clone(),
+            group: nd.group.clone(),
+            expire_timeout: nd.expire_timeout,
+            ignore: nd.ignore,
+            commands: nd.commands.clone(),
+        }
+    }
+}
+
+/// Runs every notification in `input` through the rules and writes one result per line to `output`.
+/// Returns the number of lines that could not be read.
+pub fn replay(
+    input: &mut dyn BufRead,
+    rules: &dyn EvalRules,
+    emoji_mode: EmojiMode,
+    output: &mut dyn Write,
+) -> std::io::Result<usize> {
+    let mut invalid = 0;
+    for (line_number, line) in input.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let notification = match serde_json::from_str::<Notification>(&line) {
+            Ok(n) => n,
+            Err(e) => {
+                warn!("Invalid notification in line {}: {e}", line_number + 1);
+                eprintln!("line {}: {e}", line_number + 1);
+                invalid += 1;
+                continue;
+            }
+        };
+        let mut notification_data = NotificationData::new(&notification, emoji_mode.clone());
+        let mut notification_template_data = NotificationTemplateData::from(&notification);
+        rules.eval(
+            &notification,
+            &mut notification_template_data,
+            &mut notification_data,
+        );
+        serde_json::to_writer(&mut *output, &ReplayResult::from(&notification_data))?;
+        output.write_all(b"\n")?;
+    }
+    Ok(invalid)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rule::{Action, Conditions, Definition, RuleExcutor, SetProperty, Style};
+
+    #[test]
+    fn replay_notifications() {
+        let rules = RuleExcutor::new(vec![
+            Definition {
+                conditions: vec![Conditions::AppName("mail".to_owned())],
+                actions: vec![Action::Set(SetProperty::Group("Mail".to_owned()))],
+                style: vec![Style::Background("#fff".to_owned())],
+                ..Default::default()
+            },
+            Definition {
+                conditions: vec![Conditions::Urgency("low".to_owned())],
+                actions: vec![Action::Ignore],
+                ..Default::default()
+            },
+        ]);
+        let input = concat!(
+            r#"{"app_name":"mail","id":1,"summary":"New mail","expire_timeout":5}"#,
+            "\n\n",
+            "not json\n",
+            r#"{"id":2,"summary":"Spam","urgency":"low","hints":{"category":"x"}}"#,
+            "\n"
+        );
+        let mut output = Vec::new();
+        let invalid = super::replay(
+            &mut input.as_bytes(),
+            &rules,
+            emoji::EmojiMode::Ignore,
+            &mut output,
+        )
+        .unwrap();
+        assert_eq!(invalid, 1);
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            concat!(
+                r##"{"id":1,"text":"New mail","icon":" ","style":[{"background":"#fff"}],"group":"Mail","expire_timeout":5,"ignore":false,"commands":[]}"##,
+                "\n",
+                r#"{"id":2,"text":"Spam","icon":" ","style":[],"group":null,"expire_timeout":0,"ignore":true,"commands":[]}"#,
+                "\n"
+            )
+        );
+    }
+}
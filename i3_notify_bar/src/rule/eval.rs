@@ -1,19 +1,47 @@
-use std::ops::ControlFlow;
+use std::{
+    ops::ControlFlow,
+    sync::{Arc, RwLock},
+};
 
 use crate::{
     notification_bar::{NotificationData, NotificationTemplateData},
     rule::{Action, NotificationRuleData},
+    template,
 };
 
-use super::Definition;
+use super::{Conditions, Definition};
 
 pub struct RuleExcutor {
-    rules: Vec<Definition>,
+    rules: Arc<RwLock<Vec<Definition>>>,
 }
 
 impl RuleExcutor {
     pub fn new(rules: Vec<Definition>) -> Self {
-        Self { rules }
+        Self {
+            rules: Arc::new(RwLock::new(rules)),
+        }
+    }
+
+    /// Evaluates the rules like [`EvalRules::eval`] and reports every step to `trace`.
+    pub fn trace(
+        &self,
+        n: &notify_server::notification::Notification,
+        notification_template_data: &mut NotificationTemplateData,
+        notification_data: &mut NotificationData,
+        trace: &mut dyn Trace,
+    ) {
+        let _ = trace_rules_inner(
+            &self.rules.read().unwrap_or_else(|e| e.into_inner()),
+            n,
+            notification_template_data,
+            notification_data,
+            trace,
+        );
+    }
+
+    /// Rules used by the executor. Replacing them takes effect with the next notification.
+    pub fn shared_rules(&self) -> Arc<RwLock<Vec<Definition>>> {
+        Arc::clone(&self.rules)
     }
 }
 
@@ -25,7 +53,7 @@ impl EvalRules for RuleExcutor {
         notification_data: &mut NotificationData,
     ) {
         execute_rules_inner(
-            &self.rules,
+            &self.rules.read().unwrap_or_else(|e| e.into_inner()),
             n,
             notification_template_data,
             notification_data,
@@ -33,28 +61,65 @@ impl EvalRules for RuleExcutor {
     }
 }
 
+/// Receives the steps of a rule evaluation
+pub trait Trace {
+    /// Definition `index` of the current level was checked. `failed` is the first condition that did not match.
+    fn definition(&mut self, _index: usize, _failed: Option<&Conditions>) {}
+    /// Action of the last matched definition was applied
+    fn action(&mut self, _action: &Action) {}
+    /// Sub definitions of the last matched definition are checked until the matching call to `leave`
+    fn enter(&mut self) {}
+    fn leave(&mut self) {}
+}
+
+impl Trace for () {}
+
 fn execute_rules_inner(
     definitions: &[Definition],
     n: &notify_server::notification::Notification,
     notification_template_data: &mut NotificationTemplateData,
     notification_data: &mut NotificationData,
 ) -> ControlFlow<ExecuteActionBreakReason> {
-    for rule in definitions {
+    trace_rules_inner(
+        definitions,
+        n,
+        notification_template_data,
+        notification_data,
+        &mut (),
+    )
+}
+
+fn trace_rules_inner(
+    definitions: &[Definition],
+    n: &notify_server::notification::Notification,
+    notification_template_data: &mut NotificationTemplateData,
+    notification_data: &mut NotificationData,
+    trace: &mut dyn Trace,
+) -> ControlFlow<ExecuteActionBreakReason> {
+    for (index, rule) in definitions.iter().enumerate() {
         use ExecuteActionBreakReason::*;
         let rule_data = NotificationRuleData {
             app_icon: &n.app_icon,
             app_name: &n.app_name,
-            body: &n.body,
+            body: &notification_data.body,
             expire_timeout: notification_data.expire_timeout,
             group: notification_data.group.as_deref(),
             summary: &n.summary,
             urgency: &n.urgency,
+            category: n.hints.category.as_deref(),
+            desktop_entry: n.hints.desktop_entry.as_deref(),
+            app_id: &notification_data.app_id,
+            transient: n.hints.transient,
+            resident: n.hints.resident,
         };
         if !rule.matches(&rule_data) {
+            trace.definition(index, rule.failed_condition(&rule_data));
             continue;
-        };
+        }
+        trace.definition(index, None);
         let action_result = rule.actions.iter().try_for_each(|action| {
-            excute_action(action, notification_data, notification_template_data)
+            trace.action(action);
+            excute_action(action, n, notification_data, notification_template_data)
         });
 
         match action_result {
@@ -64,13 +129,23 @@ fn execute_rules_inner(
         }
 
         notification_data.style.extend(rule.style.clone());
+        if !rule.on_click.is_empty() {
+            // Commands of click bindings get the values without pango escaping like exec actions
+            let plain_template_data = NotificationTemplateData::from(n);
+            notification_data
+                .on_click
+                .extend(rule.on_click.iter().map(|b| b.render(&plain_template_data)));
+        }
 
-        let sub_rule_result = execute_rules_inner(
+        trace.enter();
+        let sub_rule_result = trace_rules_inner(
             &rule.sub_definition,
             n,
             notification_template_data,
             notification_data,
+            trace,
         );
+        trace.leave();
         if matches!(sub_rule_result, ControlFlow::Break(_)) {
             return sub_rule_result;
         }
@@ -95,6 +170,7 @@ pub trait EvalRules {
 
 fn excute_action(
     action: &Action,
+    n: &notify_server::notification::Notification,
     notification_data: &mut NotificationData,
     notification_template_data: &mut NotificationTemplateData,
 ) -> ControlFlow<ExecuteActionBreakReason> {
@@ -109,6 +185,16 @@ fn excute_action(
             ControlFlow::Continue(())
         }
         Action::Stop => ControlFlow::Break(Stop),
+        Action::Exec(args) => {
+            // Arguments are passed to the command as they are and must not be escaped for pango
+            let plain_template_data = NotificationTemplateData::from(n);
+            notification_data.commands.push(
+                args.iter()
+                    .map(|id| template::render_template(id, &plain_template_data))
+                    .collect(),
+            );
+            ControlFlow::Continue(())
+        }
     }
 }
 
@@ -134,6 +220,20 @@ mod tests {
             remove_in_secs: None,
             style: Default::default(),
             text: Default::default(),
+            value: None,
+            app_name: Default::default(),
+            app_id: Default::default(),
+            summary: Default::default(),
+            body: Default::default(),
+            urgency: Default::default(),
+            time: 0,
+            commands: Vec::new(),
+            app_icon: Default::default(),
+            hints: Default::default(),
+            on_click: Vec::new(),
+            links: Vec::new(),
+            markup_mode: Default::default(),
+            sender: None,
         }
     }
 
@@ -147,11 +247,14 @@ mod tests {
             urgency: Urgency::Normal,
             actions: vec![],
             expire_timeout: -1,
+            hints: Default::default(),
+            sender: None,
         }
     }
     fn notification_template() -> NotificationTemplateData {
         NotificationTemplateData {
             app_name: "".into(),
+            app_id: "".into(),
             icon: "".into(),
             summary: "".into(),
             body: "".into(),
@@ -177,6 +280,146 @@ mod tests {
         assert!(nd.ignore);
     }
 
+    #[test]
+    fn replace_shared_rules() {
+        use super::{EvalRules, RuleExcutor};
+
+        let executor = RuleExcutor::new(vec![]);
+        *executor.shared_rules().write().unwrap() = vec![Definition {
+            actions: vec![Action::Ignore],
+            ..Default::default()
+        }];
+        let mut nd = notification(0);
+        executor.eval(
+            &server_notification(),
+            &mut notification_template(),
+            &mut nd,
+        );
+        assert!(nd.ignore);
+    }
+
+    #[test]
+    fn execute_rule_on_click() {
+        use crate::rule::{ClickBehaviour, ClickBinding};
+
+        let n = server_notification();
+        let mut ntd = notification_template();
+        let mut nd = notification(0);
+        let binding = |behaviour| ClickBinding {
+            button: 1,
+            modifiers: vec![],
+            behaviour,
+        };
+        super::execute_rules_inner(
+            &[Definition {
+                on_click: vec![binding(ClickBehaviour::Dismiss)],
+                sub_definition: vec![Definition {
+                    on_click: vec![binding(ClickBehaviour::Expand)],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }],
+            &n,
+            &mut ntd,
+            &mut nd,
+        );
+        assert_eq!(nd.on_click.len(), 2);
+        assert_eq!(nd.click_behaviour(1, &[]), Some(&ClickBehaviour::Expand));
+        assert_eq!(nd.click_behaviour(2, &[]), None);
+    }
+
+    #[test]
+    fn execute_rule_exec_without_pango_escaping() {
+        use crate::{
+            markup::MarkupMode,
+            rule::{ClickBehaviour, ClickBinding},
+            template,
+        };
+
+        let mut n = server_notification();
+        n.summary = "a & b".into();
+        let mut ntd = NotificationTemplateData::new(&n, MarkupMode::Pango);
+        assert_eq!(ntd.summary, "a &amp; b");
+        let mut nd = notification(0);
+        let summary = template::add_template("{{ summary }}".into()).unwrap();
+        let _ = super::execute_rules_inner(
+            &[Definition {
+                actions: vec![Action::Exec(vec![summary])],
+                on_click: vec![ClickBinding {
+                    button: 1,
+                    modifiers: vec![],
+                    behaviour: ClickBehaviour::Exec(vec![summary]),
+                }],
+                ..Default::default()
+            }],
+            &n,
+            &mut ntd,
+            &mut nd,
+        );
+        assert_eq!(nd.commands, vec![vec!["a & b".to_owned()]]);
+        assert_eq!(
+            nd.click_behaviour(1, &[]),
+            Some(&ClickBehaviour::Exec(vec!["a & b".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn trace_rules() {
+        use super::{RuleExcutor, Trace};
+
+        #[derive(Default)]
+        struct Steps(Vec<String>);
+        impl Trace for Steps {
+            fn definition(&mut self, index: usize, failed: Option<&Conditions>) {
+                self.0.push(format!("definition {index} failed {failed:?}"));
+            }
+            fn action(&mut self, action: &Action) {
+                self.0.push(format!("action {action:?}"));
+            }
+            fn enter(&mut self) {
+                self.0.push("enter".into());
+            }
+            fn leave(&mut self) {
+                self.0.push("leave".into());
+            }
+        }
+
+        let executor = RuleExcutor::new(vec![
+            Definition {
+                conditions: vec![Conditions::AppName("other".into())],
+                actions: vec![Action::Stop],
+                ..Default::default()
+            },
+            Definition {
+                sub_definition: vec![Definition {
+                    actions: vec![Action::Ignore],
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+        ]);
+        let mut steps = Steps::default();
+        let mut nd = notification(0);
+        executor.trace(
+            &server_notification(),
+            &mut notification_template(),
+            &mut nd,
+            &mut steps,
+        );
+        assert!(nd.ignore);
+        assert_eq!(
+            steps.0,
+            [
+                r#"definition 0 failed Some(AppName("other"))"#,
+                "definition 1 failed None",
+                "enter",
+                "definition 0 failed None",
+                "action Ignore",
+                "leave",
+            ]
+        );
+    }
+
     #[test]
     fn execute_rule_empty() {
         let n = server_notification();
@@ -1,14 +1,17 @@
 mod eval;
 
+use std::collections::HashSet;
+
 pub use crate::config_parser::parse_config;
 use regex::Regex;
+use serde::Serialize;
 
 use crate::{
     notification_bar::{NotificationData, NotificationTemplateData},
     template,
 };
 use emoji::{self, EmojiMode};
-pub use eval::{EvalRules, RuleExcutor};
+pub use eval::{EvalRules, RuleExcutor, Trace};
 
 pub struct NotificationRuleData<'a> {
     pub app_icon: &'a str,
@@ -18,6 +21,12 @@ pub struct NotificationRuleData<'a> {
     pub group: Option<&'a str>,
     pub urgency: &'a notify_server::notification::Urgency,
     pub expire_timeout: i32,
+    pub category: Option<&'a str>,
+    pub desktop_entry: Option<&'a str>,
+    /// Id of the matching desktop entry, see [`crate::desktop_entry::app_id`]
+    pub app_id: &'a str,
+    pub transient: bool,
+    pub resident: bool,
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -26,11 +35,40 @@ pub struct Definition {
     pub actions: Vec<Action>,
     pub style: Vec<Style>,
     pub sub_definition: Vec<Definition>,
+    pub on_click: Vec<ClickBinding<Vec<u64>>>,
+}
+
+/// Ids of all templates used by `definitions` and their sub definitions
+pub fn template_ids(definitions: &[Definition]) -> HashSet<u64> {
+    definitions
+        .iter()
+        .flat_map(|d| {
+            d.actions
+                .iter()
+                .flat_map(|a| match a {
+                    Action::Set(SetProperty::Text(id)) => std::slice::from_ref(id),
+                    Action::Exec(ids) => ids.as_slice(),
+                    _ => &[],
+                })
+                .chain(d.on_click.iter().flat_map(|b| match &b.behaviour {
+                    ClickBehaviour::Exec(ids) => ids.as_slice(),
+                    _ => &[],
+                }))
+                .copied()
+                .chain(template_ids(&d.sub_definition))
+                .collect::<Vec<_>>()
+        })
+        .collect()
 }
 
 impl Definition {
     pub fn matches(&self, notification: &NotificationRuleData) -> bool {
-        !self.conditions.iter().any(|r| !r.is_match(notification))
+        self.failed_condition(notification).is_none()
+    }
+
+    /// First condition not matching `notification`
+    pub fn failed_condition(&self, notification: &NotificationRuleData) -> Option<&Conditions> {
+        self.conditions.iter().find(|r| !r.is_match(notification))
     }
 }
 
@@ -39,6 +77,119 @@ pub enum Action {
     Ignore,
     Set(SetProperty),
     Stop,
+    /// Template ids of the command and its arguments
+    Exec(Vec<u64>),
+}
+
+/// Behaviour of a mouse button on a notification. `C` is the type of exec commands,
+/// template ids in rules and rendered arguments once applied to a notification.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClickBehaviour<C = Vec<String>> {
+    InvokeAction(String),
+    Dismiss,
+    ActionBar,
+    Copy(CopySource),
+    Expand,
+    Exec(C),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CopyField {
+    Summary,
+    Body,
+}
+
+/// Text copied to the clipboard. With a regex only the first capture group is copied,
+/// or the whole match if the regex has no groups.
+#[derive(Debug, Clone)]
+pub struct CopySource {
+    pub field: CopyField,
+    pub regex: Option<Regex>,
+}
+
+impl CopySource {
+    pub fn text(&self, nd: &NotificationData) -> Option<String> {
+        let text = match self.field {
+            CopyField::Summary => &nd.summary,
+            CopyField::Body => &nd.body,
+        };
+        let Some(regex) = &self.regex else {
+            return Some(text.clone());
+        };
+        let captures = regex.captures(text)?;
+        captures
+            .get(1)
+            .or_else(|| captures.get(0))
+            .map(|m| m.as_str().to_owned())
+    }
+}
+
+impl PartialEq for CopySource {
+    fn eq(&self, other: &Self) -> bool {
+        self.field == other.field
+            && self.regex.as_ref().map(Regex::as_str) == other.regex.as_ref().map(Regex::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickBinding<C = Vec<String>> {
+    pub button: usize,
+    /// Normalized with [`normalize_modifier`]
+    pub modifiers: Vec<String>,
+    pub behaviour: ClickBehaviour<C>,
+}
+
+impl<C> ClickBinding<C> {
+    /// Modifiers have to match exactly. Lock modifiers like num lock are ignored.
+    pub fn matches(&self, button: usize, modifiers: &[String]) -> bool {
+        if self.button != button {
+            return false;
+        }
+        let mut modifiers = modifiers
+            .iter()
+            .map(|m| normalize_modifier(m))
+            .filter(|m| !matches!(m.as_str(), "lock" | "mod2"))
+            .collect::<Vec<_>>();
+        modifiers.sort();
+        modifiers.dedup();
+        let mut expected = self.modifiers.clone();
+        expected.sort();
+        expected.dedup();
+        modifiers == expected
+    }
+}
+
+impl ClickBinding<Vec<u64>> {
+    pub fn render(&self, n: &NotificationTemplateData) -> ClickBinding {
+        let behaviour = match &self.behaviour {
+            ClickBehaviour::InvokeAction(a) => ClickBehaviour::InvokeAction(a.clone()),
+            ClickBehaviour::Dismiss => ClickBehaviour::Dismiss,
+            ClickBehaviour::ActionBar => ClickBehaviour::ActionBar,
+            ClickBehaviour::Copy(source) => ClickBehaviour::Copy(source.clone()),
+            ClickBehaviour::Expand => ClickBehaviour::Expand,
+            ClickBehaviour::Exec(ids) => ClickBehaviour::Exec(
+                ids.iter()
+                    .map(|id| template::render_template(id, n))
+                    .collect(),
+            ),
+        };
+        ClickBinding {
+            button: self.button,
+            modifiers: self.modifiers.clone(),
+            behaviour,
+        }
+    }
+}
+
+/// Maps modifier names used in the config and sent by i3bar to the same name
+pub fn normalize_modifier(modifier: &str) -> String {
+    match modifier.to_lowercase().as_str() {
+        "ctrl" | "control" => "control",
+        "alt" | "mod1" => "mod1",
+        "super" | "mod4" => "mod4",
+        m => return m.to_owned(),
+    }
+    .to_owned()
 }
 
 #[derive(Debug, PartialEq)]
@@ -76,6 +227,11 @@ pub enum Conditions {
     Group(ConditionTypeString),
     Urgency(String),
     ExpireTimeout(NumberCondition),
+    Category(ConditionTypeString),
+    DesktopEntry(ConditionTypeString),
+    AppId(ConditionTypeString),
+    Transient(bool),
+    Resident(bool),
 }
 
 impl Conditions {
@@ -101,6 +257,22 @@ impl Conditions {
             Conditions::ExpireTimeout(NumberCondition::Le(v)) => *v >= other.expire_timeout,
             Conditions::ExpireTimeout(NumberCondition::Gt(v)) => *v < other.expire_timeout,
             Conditions::ExpireTimeout(NumberCondition::Ge(v)) => *v <= other.expire_timeout,
+            Conditions::Category(ConditionTypeString::Literal(v)) => {
+                v == other.category.unwrap_or("")
+            }
+            Conditions::Category(ConditionTypeString::Regex(v)) => {
+                v.is_match(other.category.unwrap_or(""))
+            }
+            Conditions::DesktopEntry(ConditionTypeString::Literal(v)) => {
+                v == other.desktop_entry.unwrap_or("")
+            }
+            Conditions::DesktopEntry(ConditionTypeString::Regex(v)) => {
+                v.is_match(other.desktop_entry.unwrap_or(""))
+            }
+            Conditions::AppId(ConditionTypeString::Literal(v)) => v == other.app_id,
+            Conditions::AppId(ConditionTypeString::Regex(v)) => v.is_match(other.app_id),
+            Conditions::Transient(v) => *v == other.transient,
+            Conditions::Resident(v) => *v == other.resident,
         }
     }
 }
@@ -130,7 +302,8 @@ impl PartialEq for ConditionTypeString {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum Style {
     Background(String),
     Text(String),
@@ -161,9 +334,75 @@ mod tests {
             group: None,
             summary: "",
             urgency: &Urgency::Normal,
+            category: None,
+            desktop_entry: None,
+            app_id: "",
+            transient: false,
+            resident: false,
         }
     }
 
+    #[test]
+    fn template_ids_include_sub_definitions() {
+        let definitions = vec![Definition {
+            actions: vec![
+                Action::Set(SetProperty::Text(1)),
+                Action::Stop,
+                Action::Exec(vec![3, 4]),
+            ],
+            sub_definition: vec![Definition {
+                actions: vec![Action::Set(SetProperty::Text(2))],
+                ..Default::default()
+            }],
+            ..Default::default()
+        }];
+        assert_eq!(template_ids(&definitions), HashSet::from([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn click_binding_matches_normalized_modifiers() {
+        let binding = ClickBinding {
+            button: 1,
+            modifiers: vec![normalize_modifier("ctrl"), normalize_modifier("shift")],
+            behaviour: ClickBehaviour::<Vec<String>>::Dismiss,
+        };
+        let modifiers = |m: &[&str]| m.iter().map(|m| m.to_string()).collect::<Vec<_>>();
+        assert!(binding.matches(1, &modifiers(&["Shift", "Control"])));
+        assert!(binding.matches(1, &modifiers(&["Shift", "Control", "Mod2"])));
+        assert!(!binding.matches(3, &modifiers(&["Shift", "Control"])));
+        assert!(!binding.matches(1, &modifiers(&["Control"])));
+        assert!(!binding.matches(1, &modifiers(&["Shift", "Control", "Mod1"])));
+    }
+
+    #[test]
+    fn copy_source_text() {
+        let mut nd = NotificationData::new(
+            &notify_server::notification::NotificationBuilder::default()
+                .with_summary("Login".to_owned())
+                .with_body("Your code is 123456".to_owned())
+                .build(),
+            EmojiMode::Ignore,
+        );
+        let source = |field, regex: Option<&str>| CopySource {
+            field,
+            regex: regex.map(|r| Regex::new(r).unwrap()),
+        };
+        assert_eq!(
+            source(CopyField::Summary, None).text(&nd),
+            Some("Login".to_owned())
+        );
+        assert_eq!(
+            source(CopyField::Body, Some("code is (\\d+)")).text(&nd),
+            Some("123456".to_owned())
+        );
+        assert_eq!(
+            source(CopyField::Body, Some("\\d{6}")).text(&nd),
+            Some("123456".to_owned())
+        );
+        nd.body = "No code".to_owned();
+        assert_eq!(source(CopyField::Body, Some("\\d+")).text(&nd), None);
+    }
+
     #[test]
     fn definition_matches_all() {
         let mut n = new_notification();
@@ -176,6 +415,7 @@ mod tests {
             actions: Default::default(),
             style: Vec::default(),
             sub_definition: Vec::default(),
+            on_click: Vec::default(),
         };
         assert!(def.matches(&n))
     }
@@ -193,10 +433,35 @@ mod tests {
             actions: Default::default(),
             style: Vec::default(),
             sub_definition: Vec::default(),
+            on_click: Vec::default(),
         };
         assert!(!def.matches(&n))
     }
 
+    #[test]
+    fn definition_failed_condition() {
+        let mut n = new_notification();
+        n.app_name = "test-app";
+        n.expire_timeout = 9;
+        let def = Definition {
+            conditions: vec![
+                Conditions::AppName("test-app".to_owned()),
+                Conditions::ExpireTimeout(NumberCondition::Eq(10)),
+                Conditions::AppName("other-app".to_owned()),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            def.failed_condition(&n),
+            Some(&Conditions::ExpireTimeout(NumberCondition::Eq(10)))
+        );
+        n.expire_timeout = 10;
+        assert_eq!(
+            def.failed_condition(&n),
+            Some(&Conditions::AppName("other-app".to_owned()))
+        );
+    }
+
     mod action {
 
         mod set_property {
@@ -221,6 +486,20 @@ mod tests {
                     ignore: false,
                     style: Vec::default(),
                     text: "Test Text".to_owned(),
+                    value: None,
+                    app_name: Default::default(),
+                    app_id: Default::default(),
+                    summary: Default::default(),
+                    body: Default::default(),
+                    urgency: Default::default(),
+                    time: 0,
+                    commands: Vec::new(),
+                    app_icon: Default::default(),
+                    hints: Default::default(),
+                    on_click: Vec::new(),
+                    links: Vec::new(),
+                    markup_mode: Default::default(),
+                    sender: None,
                 }
             }
 
@@ -234,6 +513,8 @@ mod tests {
                     id: 0.into(),
                     summary: "Test summary".to_owned(),
                     urgency: Urgency::Normal,
+                    hints: Default::default(),
+                    sender: None,
                 })
             }
 
@@ -504,5 +785,79 @@ mod tests {
             n.group = Some("test");
             assert!(condition.is_match(&n));
         }
+
+        #[test]
+        fn category_literal() {
+            let condition =
+                Conditions::Category(ConditionTypeString::Literal(String::from("email.arrived")));
+            let mut n = new_notification();
+            assert!(!condition.is_match(&n));
+            n.category = Some("email.arrived");
+            assert!(condition.is_match(&n));
+            n.category = Some("im.received");
+            assert!(!condition.is_match(&n));
+        }
+
+        #[test]
+        fn category_regex() {
+            let condition =
+                Conditions::Category(ConditionTypeString::Regex(Regex::new("^im\\.").unwrap()));
+            let mut n = new_notification();
+            n.category = Some("im.received");
+            assert!(condition.is_match(&n));
+            n.category = Some("email.arrived");
+            assert!(!condition.is_match(&n));
+        }
+
+        #[test]
+        fn desktop_entry_literal() {
+            let condition =
+                Conditions::DesktopEntry(ConditionTypeString::Literal(String::from("slack")));
+            let mut n = new_notification();
+            assert!(!condition.is_match(&n));
+            n.desktop_entry = Some("slack");
+            assert!(condition.is_match(&n));
+        }
+
+        #[test]
+        fn desktop_entry_regex() {
+            let condition = Conditions::DesktopEntry(ConditionTypeString::Regex(
+                Regex::new("firefox$").unwrap(),
+            ));
+            let mut n = new_notification();
+            n.desktop_entry = Some("org.mozilla.firefox");
+            assert!(condition.is_match(&n));
+            n.desktop_entry = Some("chromium");
+            assert!(!condition.is_match(&n));
+        }
+
+        #[test]
+        fn app_id() {
+            let condition =
+                Conditions::AppId(ConditionTypeString::Literal("google-chrome".to_owned()));
+            let mut n = new_notification();
+            n.app_id = "google-chrome";
+            assert!(condition.is_match(&n));
+            n.app_id = "chromium";
+            assert!(!condition.is_match(&n));
+        }
+
+        #[test]
+        fn transient() {
+            let condition = Conditions::Transient(true);
+            let mut n = new_notification();
+            assert!(!condition.is_match(&n));
+            n.transient = true;
+            assert!(condition.is_match(&n));
+        }
+
+        #[test]
+        fn resident() {
+            let condition = Conditions::Resident(false);
+            let mut n = new_notification();
+            assert!(condition.is_match(&n));
+            n.resident = true;
+            assert!(!condition.is_match(&n));
+        }
     }
 }
@@ -0,0 +1,252 @@
+use std::sync::{Arc, RwLock};
+
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use log::info;
+
+use crate::notification_bar::MinimalUrgency;
+
+/// Time window during which the minimal urgency is raised.
+/// A window ending before it starts continues on the next day. Windows starting and ending at the same time last a full day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduleEntry {
+    /// Bit mask of the weekdays the window starts on. Monday is the lowest bit.
+    days: u8,
+    start: NaiveTime,
+    end: NaiveTime,
+    urgency: MinimalUrgency,
+}
+
+impl ScheduleEntry {
+    pub const ALL_DAYS: u8 = 0b111_1111;
+
+    pub fn new(days: u8, start: NaiveTime, end: NaiveTime, urgency: MinimalUrgency) -> Self {
+        Self {
+            days,
+            start,
+            end,
+            urgency,
+        }
+    }
+
+    /// Bit mask containing all days from `first` to `last` (inclusive). Ranges may wrap around the weekend.
+    pub fn day_range(first: Weekday, last: Weekday) -> u8 {
+        let mut days = 0;
+        let mut day = first;
+        loop {
+            days |= Self::day(day);
+            if day == last {
+                return days;
+            }
+            day = day.succ();
+        }
+    }
+
+    fn day(day: Weekday) -> u8 {
+        1 << day.num_days_from_monday()
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days & Self::day(day) != 0
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        let time = now.time();
+        if self.start < self.end {
+            return self.starts_on(now.weekday()) && self.start <= time && time < self.end;
+        }
+        (self.starts_on(now.weekday()) && self.start <= time)
+            || (self.starts_on((now - Duration::days(1)).weekday()) && time < self.end)
+    }
+}
+
+/// Applies the schedule to the minimal urgency shared with the menu.
+/// The urgency is only changed when a window starts or ends so it can still be changed manually.
+/// A manually changed urgency is kept once the windows are over.
+pub struct Scheduler {
+    entries: Vec<ScheduleEntry>,
+    minimal_urgency: Arc<RwLock<MinimalUrgency>>,
+    active: Option<MinimalUrgency>,
+    /// Urgency selected before the first window started
+    before: Option<MinimalUrgency>,
+}
+
+impl Scheduler {
+    pub fn new(entries: Vec<ScheduleEntry>, minimal_urgency: Arc<RwLock<MinimalUrgency>>) -> Self {
+        Self {
+            entries,
+            minimal_urgency,
+            active: None,
+            before: None,
+        }
+    }
+
+    pub fn set_entries(&mut self, entries: Vec<ScheduleEntry>) {
+        self.entries = entries;
+    }
+
+    /// Highest urgency of all windows active at `now`
+    fn active_urgency(&self, now: NaiveDateTime) -> Option<MinimalUrgency> {
+        self.entries
+            .iter()
+            .filter(|e| e.is_active(now))
+            .map(|e| e.urgency)
+            .max_by_key(|u| *u as usize)
+    }
+
+    pub fn update(&mut self, now: NaiveDateTime) {
+        let urgency = self.active_urgency(now);
+        if urgency == self.active {
+            return;
+        }
+        let mut minimal_urgency = self
+            .minimal_urgency
+            .write()
+            .unwrap_or_else(|e| e.into_inner());
+        match urgency {
+            Some(urgency) => {
+                info!("Scheduled minimal urgency {urgency:?}");
+                if self.active.is_none() {
+                    self.before = Some(*minimal_urgency);
+                }
+                *minimal_urgency = urgency;
+            }
+            None => {
+                info!("Schedule ended");
+                let before = self.before.take();
+                if let Some(before) = before.filter(|_| Some(*minimal_urgency) == self.active) {
+                    *minimal_urgency = before;
+                }
+            }
+        }
+        self.active = urgency;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use chrono::{NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+
+    use super::{ScheduleEntry, Scheduler};
+    use crate::notification_bar::MinimalUrgency;
+
+    fn time(h: u32, m: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(h, m, 0).unwrap()
+    }
+
+    /// 2024-01-01 is a monday
+    fn date_time(day: u32, h: u32, m: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_time(time(h, m))
+    }
+
+    #[test]
+    fn day_range_wraps() {
+        assert_eq!(
+            ScheduleEntry::day_range(Weekday::Mon, Weekday::Fri),
+            0b001_1111
+        );
+        assert_eq!(
+            ScheduleEntry::day_range(Weekday::Sat, Weekday::Mon),
+            0b110_0001
+        );
+        assert_eq!(
+            ScheduleEntry::day_range(Weekday::Wed, Weekday::Wed),
+            0b000_0100
+        );
+    }
+
+    #[test]
+    fn entry_is_active() {
+        let entry = ScheduleEntry::new(
+            ScheduleEntry::day_range(Weekday::Mon, Weekday::Fri),
+            time(9, 30),
+            time(9, 45),
+            MinimalUrgency::None,
+        );
+        assert!(entry.is_active(date_time(1, 9, 30)));
+        assert!(!entry.is_active(date_time(1, 9, 45)));
+        assert!(!entry.is_active(date_time(6, 9, 35)));
+    }
+
+    #[test]
+    fn entry_is_active_over_night() {
+        let entry = ScheduleEntry::new(
+            ScheduleEntry::day_range(Weekday::Fri, Weekday::Fri),
+            time(22, 0),
+            time(7, 0),
+            MinimalUrgency::Critical,
+        );
+        assert!(entry.is_active(date_time(5, 23, 0)));
+        assert!(entry.is_active(date_time(6, 6, 59)));
+        assert!(!entry.is_active(date_time(6, 7, 0)));
+        assert!(!entry.is_active(date_time(5, 6, 0)));
+    }
+
+    #[test]
+    fn entry_is_active_full_day() {
+        let entry = ScheduleEntry::new(
+            ScheduleEntry::day_range(Weekday::Mon, Weekday::Mon),
+            time(0, 0),
+            time(0, 0),
+            MinimalUrgency::Critical,
+        );
+        assert!(entry.is_active(date_time(1, 0, 0)));
+        assert!(entry.is_active(date_time(1, 23, 59)));
+        assert!(!entry.is_active(date_time(2, 0, 0)));
+    }
+
+    #[test]
+    fn scheduler_keeps_manual_urgency() {
+        let minimal_urgency = Arc::new(RwLock::new(MinimalUrgency::All));
+        let mut scheduler = Scheduler::new(
+            vec![ScheduleEntry::new(
+                ScheduleEntry::ALL_DAYS,
+                time(22, 0),
+                time(7, 0),
+                MinimalUrgency::Critical,
+            )],
+            Arc::clone(&minimal_urgency),
+        );
+        scheduler.update(date_time(1, 22, 0));
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::Critical);
+        *minimal_urgency.write().unwrap() = MinimalUrgency::None;
+        scheduler.update(date_time(2, 7, 0));
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::None);
+    }
+
+    #[test]
+    fn scheduler_restores_urgency() {
+        let minimal_urgency = Arc::new(RwLock::new(MinimalUrgency::All));
+        let mut scheduler = Scheduler::new(
+            vec![
+                ScheduleEntry::new(
+                    ScheduleEntry::ALL_DAYS,
+                    time(22, 0),
+                    time(7, 0),
+                    MinimalUrgency::Critical,
+                ),
+                ScheduleEntry::new(
+                    ScheduleEntry::ALL_DAYS,
+                    time(6, 0),
+                    time(8, 0),
+                    MinimalUrgency::Normal,
+                ),
+            ],
+            Arc::clone(&minimal_urgency),
+        );
+        scheduler.update(date_time(1, 21, 0));
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::All);
+        scheduler.update(date_time(1, 22, 0));
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::Critical);
+        // Both windows are active, the higher urgency wins
+        scheduler.update(date_time(2, 6, 30));
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::Critical);
+        scheduler.update(date_time(2, 7, 30));
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::Normal);
+        scheduler.update(date_time(2, 8, 0));
+        assert_eq!(*minimal_urgency.read().unwrap(), MinimalUrgency::All);
+    }
+}
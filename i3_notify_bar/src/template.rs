@@ -1,4 +1,7 @@
-use std::sync::{OnceLock, RwLock};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{OnceLock, RwLock},
+};
 
 use crate::notification_bar::NotificationTemplateData;
 
@@ -7,7 +10,7 @@ use mini_template::{MiniTemplate, MiniTemplateBuilder};
 
 pub const DEFAULT_TEMPLATE_ID: u64 = 0;
 
-static mut TEMPLATE_MANAGER: OnceLock<RwLock<MiniTemplate>> = OnceLock::new();
+static mut TEMPLATE_MANAGER: OnceLock<RwLock<Templates>> = OnceLock::new();
 static NEXT_TEMPLATE_ID: std::sync::atomic::AtomicU64 =
     std::sync::atomic::AtomicU64::new(DEFAULT_TEMPLATE_ID);
 
@@ -17,7 +20,10 @@ pub fn render_template(tpl_id: &u64, context: &NotificationTemplateData) -> Stri
             .get_or_init(init_template_manager)
             .read()
             .unwrap_or_else(|e| e.into_inner());
-        let output = match tplm.render(tpl_id.to_string().as_str(), context.clone().into()) {
+        let output = match tplm
+            .manager
+            .render(tpl_id.to_string().as_str(), context.clone().into())
+        {
             Ok(s) => s,
             Err(e) => e.to_string(),
         };
@@ -28,21 +34,63 @@ pub fn render_template(tpl_id: &u64, context: &NotificationTemplateData) -> Stri
 pub fn add_template(template: String) -> Result<u64, ()> {
     unsafe {
         let id = NEXT_TEMPLATE_ID.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-        let id_str = id.to_string();
-        let old_template = TEMPLATE_MANAGER
+        TEMPLATE_MANAGER
             .get_or_init(init_template_manager)
             .write()
             .unwrap_or_else(|e| e.into_inner())
-            .add_template(id_str, template);
+            .add(id, template)
+            .map(|_| id)
+    }
+}
+
+/// Removes all templates except the default template and those in `ids`.
+pub fn retain_templates(ids: &HashSet<u64>) {
+    unsafe {
+        TEMPLATE_MANAGER
+            .get_or_init(init_template_manager)
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(ids)
+    }
+}
 
-        match old_template {
-            Ok(_) => Ok(id),
+struct Templates {
+    manager: MiniTemplate,
+    /// Sources of all added templates. Needed to rebuild the manager without removed templates.
+    sources: HashMap<u64, String>,
+}
+
+impl Templates {
+    fn add(&mut self, id: u64, template: String) -> Result<(), ()> {
+        match self.manager.add_template(id.to_string(), template.clone()) {
+            Ok(_) => {
+                self.sources.insert(id, template);
+                Ok(())
+            }
             Err(_) => Err(()), // TODO return better error
         }
     }
+
+    fn retain(&mut self, ids: &HashSet<u64>) {
+        self.sources.retain(|id, _| ids.contains(id));
+        self.manager = new_mini_template();
+        for (id, template) in &self.sources {
+            // All templates have been added successfully before
+            self.manager
+                .add_template(id.to_string(), template.clone())
+                .ok();
+        }
+    }
+}
+
+fn init_template_manager() -> RwLock<Templates> {
+    RwLock::new(Templates {
+        manager: new_mini_template(),
+        sources: HashMap::new(),
+    })
 }
 
-fn init_template_manager() -> RwLock<MiniTemplate> {
+fn new_mini_template() -> MiniTemplate {
     let mut tplm = MiniTemplateBuilder::default()
         .with_default_modifiers()
         .with_modifier("date_time", &date_modifier)
@@ -57,7 +105,7 @@ fn init_template_manager() -> RwLock<MiniTemplate> {
     {
         unreachable!("Invalid default template")
     }
-    RwLock::new(tplm)
+    tplm
 }
 
 #[mini_template::macros::create_modifier]
@@ -80,3 +128,39 @@ fn max_len(text: String, len: usize) -> String {
         text
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::{init_template_manager, Templates};
+    use crate::notification_bar::NotificationTemplateData;
+
+    fn render(templates: &Templates, id: u64) -> bool {
+        let data = NotificationTemplateData {
+            app_name: String::new(),
+            app_id: String::new(),
+            icon: String::new(),
+            summary: String::new(),
+            body: String::new(),
+            expire_timeout: 0,
+            time: 0,
+        };
+        templates
+            .manager
+            .render(&id.to_string(), data.into())
+            .is_ok()
+    }
+
+    #[test]
+    fn retain_removes_unused_templates() {
+        let mut templates = init_template_manager().into_inner().unwrap();
+        templates.add(1, "one".to_owned()).unwrap();
+        templates.add(2, "two".to_owned()).unwrap();
+        templates.retain(&HashSet::from([2]));
+        assert!(render(&templates, 0));
+        assert!(!render(&templates, 1));
+        assert!(render(&templates, 2));
+        assert_eq!(templates.sources.len(), 1);
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    io::{Stdout, Write},
+    sync::{mpsc::Receiver, Arc, RwLock},
+};
+
+use log::error;
+use notify_server::notification::Urgency;
+use serde::Serialize;
+
+use crate::{
+    markup::{self, MarkupMode},
+    notification_bar::{MinimalUrgency, NotificationData, NotificationEvent},
+    output::Bar,
+};
+
+/// Summary of all notifications for a waybar `custom` module with `"return-type": "json"`.
+/// A line is only written if the summary changed.
+pub struct Waybar<W: Write = Stdout> {
+    out_writer: W,
+    events: Receiver<NotificationEvent>,
+    notifications: Vec<Arc<RwLock<NotificationData>>>,
+    queued: usize,
+    minimal_urgency: Arc<RwLock<MinimalUrgency>>,
+    config_errors: Arc<RwLock<Vec<String>>>,
+    last_line: Option<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+struct WaybarLine {
+    /// Text of the most important notification
+    text: String,
+    /// Config errors followed by the texts of all notifications
+    tooltip: String,
+    /// `error` if the config contains errors, otherwise the urgency of the most important
+    /// notification or `empty`
+    class: &'static str,
+    /// `notification` or `none`, prefixed with `dnd-` while do not disturb is active.
+    /// Meant to be used with `format-icons`.
+    alt: String,
+    /// Progress of the most important notification
+    #[serde(skip_serializing_if = "Option::is_none")]
+    percentage: Option<u8>,
+}
+
+impl<W: Write> Waybar<W> {
+    pub fn new(
+        out_writer: W,
+        events: Receiver<NotificationEvent>,
+        minimal_urgency: Arc<RwLock<MinimalUrgency>>,
+        config_errors: Arc<RwLock<Vec<String>>>,
+    ) -> Self {
+        Self {
+            out_writer,
+            events,
+            notifications: Vec::new(),
+            queued: 0,
+            minimal_urgency,
+            config_errors,
+            last_line: None,
+        }
+    }
+
+    fn summary(&self) -> WaybarLine {
+        let notifications = self
+            .notifications
+            .iter()
+            .map(|n| n.read().unwrap_or_else(|e| e.into_inner()))
+            .collect::<Vec<_>>();
+        // Newest notification with the highest urgency
+        let top = notifications
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, n)| (n.urgency as usize, *index))
+            .map(|(_, n)| n);
+
+        let config_errors = self.config_errors.read().unwrap_or_else(|e| e.into_inner());
+        let count = notifications.len();
+        let text = match top {
+            Some(top) if count > 1 => format!("{} (+{})", markup_text(top), count - 1),
+            Some(top) => markup_text(top),
+            // Waybar hides modules with an empty text
+            None if !config_errors.is_empty() => format!(
+                "{} config error{}",
+                config_errors.len(),
+                if config_errors.len() == 1 { "" } else { "s" }
+            ),
+            None => String::new(),
+        };
+        let mut tooltip = config_errors
+            .iter()
+            .map(|e| markup::escape(e))
+            .chain(notifications.iter().rev().map(|n| markup_text(n)))
+            .collect::<Vec<_>>();
+        if self.queued > 0 {
+            tooltip.push(format!("{} queued", self.queued));
+        }
+
+        let dnd = *self
+            .minimal_urgency
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            == MinimalUrgency::None;
+        let alt = match (dnd, count) {
+            (false, 0) => "none",
+            (false, _) => "notification",
+            (true, 0) => "dnd-none",
+            (true, _) => "dnd-notification",
+        };
+
+        WaybarLine {
+            text,
+            tooltip: tooltip.join("\n"),
+            class: match top {
+                _ if !config_errors.is_empty() => "error",
+                Some(n) => match n.urgency {
+                    Urgency::Low => "low",
+                    Urgency::Normal => "normal",
+                    Urgency::Critical => "critical",
+                },
+                None => "empty",
+            },
+            alt: alt.to_owned(),
+            percentage: top.and_then(|n| n.value),
+        }
+    }
+}
+
+impl<W: Write> Bar for Waybar<W> {
+    fn update(&mut self) {
+        for event in self.events.try_iter() {
+            match event {
+                NotificationEvent::Add(n) => self.notifications.push(n),
+                NotificationEvent::Remove(n) => self.notifications.retain(|o| !Arc::ptr_eq(o, &n)),
+                NotificationEvent::Queued(count) => self.queued = count,
+            }
+        }
+
+        let line = match serde_json::to_string(&self.summary()) {
+            Ok(line) => line,
+            Err(e) => {
+                error!("Could not serialize waybar line: {e}");
+                return;
+            }
+        };
+        if self.last_line.as_ref() == Some(&line) {
+            return;
+        }
+        if let Err(e) = writeln!(self.out_writer, "{line}").and_then(|_| self.out_writer.flush()) {
+            error!("Could not write bytes: {:#?}", e);
+        }
+        self.last_line = Some(line);
+    }
+}
+
+/// Icon and text of a notification as pango markup
+fn markup_text(n: &NotificationData) -> String {
+    let text = match n.markup_mode {
+        MarkupMode::Strip => markup::escape(&n.text),
+        MarkupMode::Pango => n.text.clone(),
+    };
+    format!("{} {}", n.icon, text).trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, RwLock};
+
+    use notify_server::notification::{Notification, Urgency};
+
+    use super::{Waybar, WaybarLine};
+    use crate::{
+        notification_bar::{MinimalUrgency, NotificationData, NotificationEvent},
+        output::Bar as _,
+    };
+
+    fn notification(text: &str, urgency: Urgency) -> Arc<RwLock<NotificationData>> {
+        let mut n = NotificationData::new(&Notification::default(), emoji::EmojiMode::Ignore);
+        n.text = text.to_owned();
+        n.urgency = urgency;
+        Arc::new(RwLock::new(n))
+    }
+
+    #[test]
+    fn summarize_notifications() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let minimal_urgency = Arc::new(RwLock::new(MinimalUrgency::Normal));
+        let mut waybar = Waybar::new(
+            Vec::new(),
+            rx,
+            Arc::clone(&minimal_urgency),
+            Default::default(),
+        );
+
+        waybar.update();
+        let critical = notification("disk full", Urgency::Critical);
+        critical.write().unwrap().value = Some(97);
+        tx.send(NotificationEvent::Add(critical)).unwrap();
+        tx.send(NotificationEvent::Add(notification(
+            "a & b",
+            Urgency::Normal,
+        )))
+        .unwrap();
+        tx.send(NotificationEvent::Queued(1)).unwrap();
+        waybar.update();
+        assert_eq!(
+            waybar.summary(),
+            WaybarLine {
+                text: "disk full (+1)".into(),
+                tooltip: "a &amp; b\ndisk full\n1 queued".into(),
+                class: "critical",
+                alt: "notification".into(),
+                percentage: Some(97),
+            }
+        );
+
+        // Unchanged summaries are not written again
+        waybar.update();
+        *minimal_urgency.write().unwrap() = MinimalUrgency::None;
+        waybar.update();
+
+        let written = String::from_utf8(waybar.out_writer).unwrap();
+        let lines = written.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[0],
+            r#"{"text":"","tooltip":"","class":"empty","alt":"none"}"#
+        );
+        assert!(lines[2].contains(r#""alt":"dnd-notification""#));
+    }
+
+    #[test]
+    fn remove_notification() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let minimal_urgency = Arc::new(RwLock::new(MinimalUrgency::Normal));
+        let mut waybar = Waybar::new(Vec::new(), rx, minimal_urgency, Default::default());
+        let n = notification("first", Urgency::Low);
+        tx.send(NotificationEvent::Add(Arc::clone(&n))).unwrap();
+        tx.send(NotificationEvent::Add(notification("second", Urgency::Low)))
+            .unwrap();
+        tx.send(NotificationEvent::Remove(n)).unwrap();
+        waybar.update();
+        let summary = waybar.summary();
+        assert_eq!(summary.text, "second");
+        assert_eq!(summary.class, "low");
+    }
+
+    #[test]
+    fn show_config_errors() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let minimal_urgency = Arc::new(RwLock::new(MinimalUrgency::Normal));
+        let config_errors = Arc::new(RwLock::new(vec!["config:3:9: Unknown icon <x>".to_owned()]));
+        let mut waybar = Waybar::new(Vec::new(), rx, minimal_urgency, Arc::clone(&config_errors));
+        waybar.update();
+        let summary = waybar.summary();
+        assert_eq!(summary.text, "1 config error");
+        assert_eq!(summary.tooltip, "config:3:9: Unknown icon &lt;x&gt;");
+        assert_eq!(summary.class, "error");
+
+        tx.send(NotificationEvent::Add(notification("a", Urgency::Low)))
+            .unwrap();
+        waybar.update();
+        let summary = waybar.summary();
+        assert_eq!(summary.text, "a");
+        assert_eq!(summary.tooltip, "config:3:9: Unknown icon &lt;x&gt;\na");
+        assert_eq!(summary.class, "error");
+
+        config_errors.write().unwrap().clear();
+        assert_eq!(waybar.summary().class, "low");
+    }
+}
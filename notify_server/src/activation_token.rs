@@ -0,0 +1,40 @@
+use std::{
+    sync::atomic::{AtomicU32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+/// Creates a unique token in the startup notification format `<unique>_TIME<timestamp>`.
+/// Applications pass it to xdg-activation or use it as startup id to raise their window.
+pub fn generate() -> String {
+    let counter = COUNTER.fetch_add(1, Ordering::Relaxed);
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    format!(
+        "i3_notify_bar-{}-{}_TIME{}",
+        std::process::id(),
+        counter,
+        time
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn tokens_are_unique() {
+        let first = super::generate();
+        let second = super::generate();
+        assert_ne!(first, second);
+
+        let prefix = format!("i3_notify_bar-{}-", std::process::id());
+        let (counter, time) = first
+            .strip_prefix(&prefix)
+            .unwrap()
+            .split_once("_TIME")
+            .unwrap();
+        assert!(counter.parse::<u32>().is_ok());
+        assert!(time.parse::<u128>().unwrap() > 0);
+    }
+}
@@ -2,6 +2,6 @@ use crate::{notification::Notification, CloseReason, NotificationId};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Event {
-    Notify(Notification),
+    Notify(Box<Notification>),
     Close(NotificationId, CloseReason),
 }
@@ -1,3 +1,4 @@
+mod activation_token;
 mod events;
 pub mod notification;
 mod notify_server;
@@ -10,8 +11,10 @@ pub use crate::notify_server::{
     Message as NotificationMessage, MockNotificationSource, NotificationSource, NotifyServer,
 };
 pub use events::Event;
+pub use notify_server_free_desktop::is_connected;
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
 pub struct NotificationId(u32);
 
 pub struct Options {
@@ -24,6 +27,12 @@ impl From<u32> for NotificationId {
     }
 }
 
+impl From<NotificationId> for u32 {
+    fn from(id: NotificationId) -> u32 {
+        id.0
+    }
+}
+
 impl Display for NotificationId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         self.0.fmt(f)
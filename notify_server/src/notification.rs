@@ -1,9 +1,12 @@
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 use zbus::zvariant::Value;
 
 use crate::NotificationId;
 
-#[derive(Debug, Clone, PartialEq, jbe::Builder)]
+/// Missing fields are filled with their default values when deserializing.
+#[derive(Debug, Clone, PartialEq, jbe::Builder, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Notification {
     #[builder({default: String::default()})]
     pub app_name: String,
@@ -21,11 +24,23 @@ pub struct Notification {
     pub actions: Vec<Action>,
     #[builder({default: 0})]
     pub expire_timeout: i32,
+    #[builder({default: Hints::default()})]
+    pub hints: Hints,
+    /// Unique bus name of the application which sent the notification
+    #[builder({default: None})]
+    pub sender: Option<String>,
 }
 
 unsafe impl Sync for Notification {}
 
-#[derive(Debug, Clone, PartialEq, Copy, Eq, Hash, Default)]
+impl Default for Notification {
+    fn default() -> Self {
+        NotificationBuilder::default().build()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Copy, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Urgency {
     Low = 0,
     #[default]
@@ -74,8 +89,122 @@ impl PartialOrd for Urgency {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Action {
     pub key: String,
     pub text: String,
 }
+
+/// Hints defined by the freedesktop notification specification.
+/// `urgency` is stored directly on the notification.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Hints {
+    pub action_icons: bool,
+    pub category: Option<String>,
+    pub desktop_entry: Option<String>,
+    pub resident: bool,
+    pub sound_file: Option<String>,
+    pub sound_name: Option<String>,
+    pub suppress_sound: bool,
+    pub transient: bool,
+    pub x: Option<i32>,
+    pub y: Option<i32>,
+    /// Progress in percent. Not part of the specification but widely used.
+    pub value: Option<i32>,
+}
+
+impl Hints {
+    /// Stores a single hint. Unknown hints and hints with an unexpected type are ignored.
+    pub fn set(&mut self, key: &str, value: &Value) {
+        match key {
+            "action-icons" => set_hint(&mut self.action_icons, value_to_bool(value)),
+            "category" => self.category = value_to_string(value),
+            "desktop-entry" => self.desktop_entry = value_to_string(value),
+            "resident" => set_hint(&mut self.resident, value_to_bool(value)),
+            "sound-file" => self.sound_file = value_to_string(value),
+            "sound-name" => self.sound_name = value_to_string(value),
+            "suppress-sound" => set_hint(&mut self.suppress_sound, value_to_bool(value)),
+            "transient" => set_hint(&mut self.transient, value_to_bool(value)),
+            "x" => self.x = value_to_i32(value),
+            "y" => self.y = value_to_i32(value),
+            "value" => self.value = value_to_i32(value),
+            _ => return,
+        }
+        log::trace!("Hint {key}: {value:?}");
+    }
+}
+
+fn set_hint<T>(hint: &mut T, value: Option<T>) {
+    if let Some(value) = value {
+        *hint = value;
+    }
+}
+
+fn value_to_bool(value: &Value) -> Option<bool> {
+    match value {
+        Value::Bool(b) => Some(*b),
+        // Some clients send booleans as integers
+        value => value_to_i32(value).map(|v| v != 0),
+    }
+}
+
+fn value_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Str(s) => Some(s.to_string()),
+        _ => None,
+    }
+}
+
+fn value_to_i32(value: &Value) -> Option<i32> {
+    match value {
+        Value::U8(v) => Some((*v).into()),
+        Value::I16(v) => Some((*v).into()),
+        Value::U16(v) => Some((*v).into()),
+        Value::I32(v) => Some(*v),
+        Value::U32(v) => i32::try_from(*v).ok(),
+        Value::I64(v) => i32::try_from(*v).ok(),
+        Value::U64(v) => i32::try_from(*v).ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use zbus::zvariant::Value;
+
+    use super::Hints;
+
+    #[test]
+    fn hints_set() {
+        let mut hints = Hints::default();
+        hints.set("category", &Value::from("email.arrived"));
+        hints.set("desktop-entry", &Value::from("thunderbird"));
+        hints.set("transient", &Value::from(true));
+        hints.set("resident", &Value::from(1u8));
+        hints.set("value", &Value::from(42u32));
+        hints.set("x", &Value::from(-10));
+        hints.set("unknown", &Value::from("ignored"));
+        assert_eq!(
+            hints,
+            Hints {
+                category: Some("email.arrived".into()),
+                desktop_entry: Some("thunderbird".into()),
+                transient: true,
+                resident: true,
+                value: Some(42),
+                x: Some(-10),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn hints_set_wrong_type() {
+        let mut hints = Hints::default();
+        hints.set("category", &Value::from(12));
+        hints.set("transient", &Value::from("true"));
+        hints.set("value", &Value::from(u64::MAX));
+        assert_eq!(hints, Hints::default());
+    }
+}
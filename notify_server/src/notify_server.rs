@@ -1,12 +1,19 @@
-use std::sync::{Arc, Mutex, PoisonError};
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex, PoisonError},
+};
 
-use crate::{notify_server_free_desktop::NotifyServerFreeDesktop, Event, NotificationId};
+use crate::{
+    activation_token, notify_server_free_desktop::NotifyServerFreeDesktop, Event, NotificationId,
+};
 
 #[allow(async_fn_in_trait)]
 #[mockall::automock]
 pub trait NotifyServerInterface {
     async fn action_invoked(&self, id: NotificationId, action: &str) -> zbus::Result<()>;
 
+    async fn activation_token(&self, id: NotificationId, token: &str) -> zbus::Result<()>;
+
     async fn notification_closed(
         &self,
         id: NotificationId,
@@ -17,6 +24,9 @@ pub trait NotifyServerInterface {
 pub struct NotifyServer<Interface: NotifyServerInterface = NotifyServerFreeDesktop> {
     interface_ref: Interface,
     events: Arc<Mutex<Vec<Event>>>,
+    /// Ids of notifications which have not been closed yet. Shared with the D-Bus interface to decide
+    /// if `replaces_id` refers to an open notification.
+    active_ids: Arc<Mutex<HashSet<u32>>>,
 }
 
 #[mockall::automock]
@@ -36,13 +46,33 @@ impl NotifyServer<NotifyServerFreeDesktop> {
     #[cfg(not(test))]
     pub fn start() -> zbus::Result<Self> {
         let events = Default::default();
+        let active_ids = Default::default();
         Ok(Self {
-            interface_ref: NotifyServerFreeDesktop::new(Arc::clone(&events))?,
+            interface_ref: NotifyServerFreeDesktop::new(
+                Arc::clone(&events),
+                Arc::clone(&active_ids),
+            )?,
             events,
+            active_ids,
         })
     }
 }
 
+#[cfg(test)]
+impl<I: NotifyServerInterface> NotifyServer<I> {
+    pub(crate) fn with_interface(
+        interface_ref: I,
+        events: Arc<Mutex<Vec<Event>>>,
+        active_ids: Arc<Mutex<HashSet<u32>>>,
+    ) -> Self {
+        Self {
+            interface_ref,
+            events,
+            active_ids,
+        }
+    }
+}
+
 impl<I: NotifyServerInterface> NotificationSource for NotifyServer<I> {
     fn take_events(&mut self) -> Option<Vec<Event>> {
         let mut events = self.events.lock().unwrap_or_else(PoisonError::into_inner);
@@ -53,7 +83,12 @@ impl<I: NotifyServerInterface> NotificationSource for NotifyServer<I> {
         }
     }
 
+    /// Sends an activation token before the action so the application is allowed to raise its window.
     async fn action_invoked(&self, id: NotificationId, action: &str) -> zbus::Result<()> {
+        let token = activation_token::generate();
+        if let Err(e) = self.interface_ref.activation_token(id, &token).await {
+            log::warn!("Could not send activation token for notification {id}: {e}");
+        }
         self.interface_ref.action_invoked(id, action).await
     }
 
@@ -62,6 +97,10 @@ impl<I: NotifyServerInterface> NotificationSource for NotifyServer<I> {
         id: NotificationId,
         reason: &CloseReason,
     ) -> zbus::Result<()> {
+        self.active_ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(&id.0);
         self.events
             .lock()
             .unwrap_or_else(PoisonError::into_inner)
@@ -87,7 +126,8 @@ pub enum Message {
 #[cfg(test)]
 mod tests {
     use crate::{
-        notify_server::NotifyServerInterface, CloseReason, Event, NotificationSource, NotifyServer,
+        notify_server::{MockNotifyServerInterface, NotifyServerInterface},
+        CloseReason, Event, NotificationSource, NotifyServer,
     };
 
     #[async_std::test]
@@ -98,6 +138,13 @@ mod tests {
             async fn action_invoked(&self, _: crate::NotificationId, _: &str) -> zbus::Result<()> {
                 Ok(())
             }
+            async fn activation_token(
+                &self,
+                _: crate::NotificationId,
+                _: &str,
+            ) -> zbus::Result<()> {
+                Ok(())
+            }
             async fn notification_closed(
                 &self,
                 _: crate::NotificationId,
@@ -109,6 +156,7 @@ mod tests {
 
         let mut notify_server = NotifyServer {
             events: Default::default(),
+            active_ids: Default::default(),
             interface_ref: NotifyInterface,
         };
 
@@ -120,4 +168,60 @@ mod tests {
         assert_eq!(events.len(), 1);
         assert_eq!(events[0], Event::Close(24.into(), CloseReason::Dismissed));
     }
+
+    #[async_std::test]
+    async fn action_invoked_sends_activation_token_first() {
+        use mockall::{predicate::eq, Sequence};
+
+        let mut interface = MockNotifyServerInterface::new();
+        let mut sequence = Sequence::new();
+        interface
+            .expect_activation_token()
+            .once()
+            .in_sequence(&mut sequence)
+            .withf(|id, token| {
+                *id == 3.into()
+                    && token.starts_with(&format!("i3_notify_bar-{}-", std::process::id()))
+            })
+            .returning(|_, _| Ok(()));
+        interface
+            .expect_action_invoked()
+            .once()
+            .in_sequence(&mut sequence)
+            .with(eq::<crate::NotificationId>(3.into()), eq("default"))
+            .returning(|_, _| Ok(()));
+
+        let notify_server = NotifyServer {
+            events: Default::default(),
+            active_ids: Default::default(),
+            interface_ref: interface,
+        };
+        notify_server
+            .action_invoked(3.into(), "default")
+            .await
+            .unwrap();
+    }
+
+    #[async_std::test]
+    async fn action_invoked_without_activation_token() {
+        let mut interface = MockNotifyServerInterface::new();
+        interface
+            .expect_activation_token()
+            .once()
+            .returning(|_, _| Err(zbus::Error::Unsupported));
+        interface
+            .expect_action_invoked()
+            .once()
+            .returning(|_, _| Ok(()));
+
+        let notify_server = NotifyServer {
+            events: Default::default(),
+            active_ids: Default::default(),
+            interface_ref: interface,
+        };
+        notify_server
+            .action_invoked(3.into(), "default")
+            .await
+            .unwrap();
+    }
 }
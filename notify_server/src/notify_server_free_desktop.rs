@@ -1,24 +1,35 @@
 use std::{
-    collections::HashMap,
-    sync::{Arc, Mutex},
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex, PoisonError},
 };
 
 use crate::{
-    notification::NotificationBuilder, notify_server::NotifyServerInterface, CloseReason, Event,
-    NotificationId,
+    notification::{Hints, NotificationBuilder},
+    notify_server::NotifyServerInterface,
+    CloseReason, Event, NotificationId,
 };
-use zbus::{blocking::InterfaceRef, dbus_interface, SignalContext};
+use zbus::{blocking::InterfaceRef, dbus_interface, MessageHeader, SignalContext};
 //#[cfg(not(test))]
 use zbus::blocking::{Connection, ConnectionBuilder};
 use zvariant::Value;
 
+/// Capabilities implemented by the server and the bar
+const CAPABILITIES: &[&str] = &["actions", "body", "body-hyperlinks", "body-markup"];
+
 pub struct NotifyServerFreeDesktop {
     interface: InterfaceRef<NotifyServerInterfaceDBus>,
 }
 
 impl NotifyServerFreeDesktop {
-    pub fn new(events: Arc<Mutex<Vec<Event>>>) -> zbus::Result<Self> {
-        let interface = NotifyServerInterfaceDBus { events, last_id: 0 };
+    pub fn new(
+        events: Arc<Mutex<Vec<Event>>>,
+        active_ids: Arc<Mutex<HashSet<u32>>>,
+    ) -> zbus::Result<Self> {
+        let interface = NotifyServerInterfaceDBus {
+            events,
+            last_id: 0,
+            active_ids,
+        };
         let connection = interface.run()?;
         let i = connection
             .object_server()
@@ -33,6 +44,11 @@ impl NotifyServerInterface for NotifyServerFreeDesktop {
         NotifyServerInterfaceDBus::action_invoked(context, id.0, action).await
     }
 
+    async fn activation_token(&self, id: NotificationId, token: &str) -> zbus::Result<()> {
+        let context = self.interface.signal_context();
+        NotifyServerInterfaceDBus::activation_token(context, id.0, token).await
+    }
+
     async fn notification_closed(
         &self,
         id: NotificationId,
@@ -43,10 +59,25 @@ impl NotifyServerInterface for NotifyServerFreeDesktop {
     }
 }
 
+/// Checks if the application with the given unique bus name is still connected to the session bus.
+pub fn is_connected(sender: &str) -> bool {
+    let has_owner = || -> zbus::Result<bool> {
+        let connection = Connection::session()?;
+        let proxy = zbus::blocking::fdo::DBusProxy::new(&connection)?;
+        Ok(proxy.name_has_owner(sender.try_into()?)?)
+    };
+    has_owner().unwrap_or_else(|e| {
+        log::warn!("Could not check if {sender} is connected: {e}");
+        false
+    })
+}
+
 #[derive(Default)]
 struct NotifyServerInterfaceDBus {
     events: Arc<Mutex<Vec<Event>>>,
     last_id: u32,
+    /// Ids of notifications which have not been closed yet
+    active_ids: Arc<Mutex<HashSet<u32>>>,
 }
 
 impl NotifyServerInterfaceDBus {
@@ -67,24 +98,11 @@ impl NotifyServerInterfaceDBus {
             .unwrap_or_else(|e| e.into_inner())
             .push(ev);
     }
-}
 
-#[allow(clippy::too_many_arguments)]
-#[dbus_interface(name = "org.freedesktop.Notifications")]
-impl NotifyServerInterfaceDBus {
-    fn get_capabilities(&self) -> Vec<&str> {
-        vec![
-            "action-icons",
-            "actions",
-            "body",
-            "body-hyperlinks",
-            "body-markup",
-            "persistence",
-        ]
-    }
-
-    fn notify(
+    #[allow(clippy::too_many_arguments)]
+    fn notify_from(
         &mut self,
+        sender: Option<String>,
         app_name: String,
         replaces_id: u32,
         app_icon: String,
@@ -99,20 +117,16 @@ impl NotifyServerInterfaceDBus {
             .with_app_icon(app_icon)
             .with_summary(summary)
             .with_body(body)
-            .with_expire_timeout(expire_timeout);
-        let id = match replaces_id {
-            0 => {
-                self.last_id += 1;
-                self.last_id
-            }
-            id => id,
-        };
+            .with_expire_timeout(expire_timeout)
+            .with_sender(sender);
+        let id = self.assign_id(replaces_id);
         builder.set_id(id.into());
-        hints.into_iter().for_each(|(key, hint)| {
-            if &key[..] == "urgency" {
-                builder.set_urgency(hint.into())
-            }
+        let mut parsed_hints = Hints::default();
+        hints.into_iter().for_each(|(key, hint)| match &key[..] {
+            "urgency" => builder.set_urgency(hint.into()),
+            key => parsed_hints.set(key, &hint),
         });
+        builder.set_hints(parsed_hints);
 
         let mut actions_vec = Vec::with_capacity(actions.len() / 2);
         // TODO change to group_by once https://github.com/rust-lang/rust/issues/80552 is stable
@@ -128,10 +142,62 @@ impl NotifyServerInterfaceDBus {
 
         let notification = builder.build();
 
-        self.push_event(Event::Notify(notification));
+        self.push_event(Event::Notify(Box::new(notification)));
         id
     }
 
+    /// Reuses `replaces_id` if it belongs to an active notification. Otherwise a new id is allocated.
+    fn assign_id(&mut self, replaces_id: u32) -> u32 {
+        let mut active_ids = self
+            .active_ids
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        if replaces_id != 0 && active_ids.contains(&replaces_id) {
+            return replaces_id;
+        }
+        loop {
+            // 0 is never a valid id
+            self.last_id = self.last_id.wrapping_add(1).max(1);
+            if active_ids.insert(self.last_id) {
+                return self.last_id;
+            }
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+#[dbus_interface(name = "org.freedesktop.Notifications")]
+impl NotifyServerInterfaceDBus {
+    fn get_capabilities(&self) -> Vec<&str> {
+        CAPABILITIES.to_vec()
+    }
+
+    fn notify(
+        &mut self,
+        #[zbus(header)] header: MessageHeader<'_>,
+        app_name: String,
+        replaces_id: u32,
+        app_icon: String,
+        summary: String,
+        body: String,
+        actions: Vec<String>,
+        hints: HashMap<String, Value>,
+        expire_timeout: i32,
+    ) -> u32 {
+        let sender = header.sender().ok().flatten().map(|s| s.to_string());
+        self.notify_from(
+            sender,
+            app_name,
+            replaces_id,
+            app_icon,
+            summary,
+            body,
+            actions,
+            hints,
+            expire_timeout,
+        )
+    }
+
     fn close_notification(&mut self, id: u32) {
         self.push_event(Event::Close(id.into(), CloseReason::RequesedByClient));
     }
@@ -140,7 +206,7 @@ impl NotifyServerInterfaceDBus {
         (
             "i3_notify_bar_notification_server",
             "Julian Alberts",
-            "0.1",
+            env!("CARGO_PKG_VERSION"),
             "1.2",
         )
     }
@@ -152,6 +218,13 @@ impl NotifyServerInterfaceDBus {
         action: &str,
     ) -> zbus::Result<()>;
 
+    #[dbus_interface(signal)]
+    async fn activation_token(
+        signal_ctxt: &SignalContext<'_>,
+        id: u32,
+        activation_token: &str,
+    ) -> zbus::Result<()>;
+
     #[dbus_interface(signal)]
     async fn notification_closed(
         signal_ctxt: &SignalContext<'_>,
@@ -163,14 +236,17 @@ impl NotifyServerInterfaceDBus {
 #[cfg(test)]
 mod interface_tests {
 
-    use std::collections::HashMap;
+    use std::{collections::HashMap, sync::Arc};
+
+    use zvariant::Value;
 
     use crate::{
-        notification::Notification, notify_server_free_desktop::NotifyServerInterfaceDBus, Event,
+        notification::{Hints, Notification, Urgency},
+        notify_server::MockNotifyServerInterface,
+        notify_server_free_desktop::NotifyServerInterfaceDBus,
+        CloseReason, Event, NotificationSource, NotifyServer,
     };
 
-    use super::NotifyServerInterface;
-
     #[test]
     fn notify() {
         let app_name = String::from("my app name");
@@ -190,7 +266,8 @@ mod interface_tests {
 
         let mut interface = NotifyServerInterfaceDBus::default();
 
-        interface.notify(
+        interface.notify_from(
+            Some(String::from(":1.42")),
             app_name_cp,
             replace_id,
             app_icon_cp,
@@ -203,7 +280,7 @@ mod interface_tests {
 
         assert_eq!(
             interface.events.lock().unwrap()[0],
-            Event::Notify(Notification {
+            Event::Notify(Box::new(Notification {
                 actions: vec![],
                 app_icon,
                 app_name,
@@ -211,10 +288,176 @@ mod interface_tests {
                 expire_timeout,
                 id: 1.into(),
                 summary,
-                urgency: crate::notification::Urgency::Normal
-            })
+                urgency: crate::notification::Urgency::Normal,
+                hints: Default::default(),
+                sender: Some(String::from(":1.42")),
+            }))
         );
 
         assert_eq!(interface.last_id, 1);
     }
+
+    #[test]
+    fn notify_hints() {
+        let mut interface = NotifyServerInterfaceDBus::default();
+        let hints = HashMap::from([
+            ("urgency".to_owned(), Value::from(2u8)),
+            ("category".to_owned(), Value::from("im.received")),
+            ("desktop-entry".to_owned(), Value::from("slack")),
+            ("suppress-sound".to_owned(), Value::from(true)),
+            ("sound-name".to_owned(), Value::from("message-new-instant")),
+            ("y".to_owned(), Value::from(20)),
+            ("value".to_owned(), Value::from(75)),
+        ]);
+
+        interface.notify_from(
+            None,
+            String::new(),
+            0,
+            String::new(),
+            String::new(),
+            String::new(),
+            vec![],
+            hints,
+            0,
+        );
+
+        let events = interface.events.lock().unwrap();
+        let Event::Notify(notification) = &events[0] else {
+            panic!("Expected notify event")
+        };
+        assert_eq!(notification.urgency, Urgency::Critical);
+        assert_eq!(
+            notification.hints,
+            Hints {
+                category: Some("im.received".into()),
+                desktop_entry: Some("slack".into()),
+                suppress_sound: true,
+                sound_name: Some("message-new-instant".into()),
+                y: Some(20),
+                value: Some(75),
+                ..Default::default()
+            }
+        );
+    }
+
+    fn notify_replacing(interface: &mut NotifyServerInterfaceDBus, replaces_id: u32) -> u32 {
+        interface.notify_from(
+            None,
+            String::new(),
+            replaces_id,
+            String::new(),
+            String::new(),
+            String::new(),
+            vec![],
+            HashMap::new(),
+            0,
+        )
+    }
+
+    #[test]
+    fn notify_replaces_active_notification() {
+        let mut interface = NotifyServerInterfaceDBus::default();
+        assert_eq!(notify_replacing(&mut interface, 0), 1);
+        assert_eq!(notify_replacing(&mut interface, 1), 1);
+        assert_eq!(notify_replacing(&mut interface, 0), 2);
+
+        let events = interface.events.lock().unwrap();
+        let ids = events
+            .iter()
+            .map(|e| match e {
+                Event::Notify(n) => u32::from(n.id),
+                Event::Close(..) => panic!("Expected notify event"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ids, [1, 1, 2]);
+    }
+
+    #[async_std::test]
+    async fn notify_unknown_replaces_id_allocates_new_id() {
+        let mut dbus_interface = MockNotifyServerInterface::new();
+        dbus_interface
+            .expect_notification_closed()
+            .returning(|_, _| Ok(()));
+        let mut interface = NotifyServerInterfaceDBus::default();
+        let mut notify_server = NotifyServer::with_interface(
+            dbus_interface,
+            Arc::clone(&interface.events),
+            Arc::clone(&interface.active_ids),
+        );
+
+        assert_eq!(notify_replacing(&mut interface, 42), 1);
+        assert_eq!(notify_replacing(&mut interface, 0), 2);
+        notify_server
+            .notification_closed(1.into(), &CloseReason::Expired)
+            .await
+            .unwrap();
+        assert_eq!(notify_replacing(&mut interface, 1), 3);
+        assert_eq!(notify_replacing(&mut interface, 2), 2);
+    }
+
+    #[test]
+    fn notify_skips_active_ids_on_overflow() {
+        let mut interface = NotifyServerInterfaceDBus {
+            last_id: u32::MAX - 1,
+            ..Default::default()
+        };
+        interface.active_ids.lock().unwrap().insert(1);
+        assert_eq!(notify_replacing(&mut interface, 0), u32::MAX);
+        assert_eq!(notify_replacing(&mut interface, 0), 2);
+    }
+
+    #[test]
+    fn close_notification() {
+        let mut interface = NotifyServerInterfaceDBus::default();
+        interface.close_notification(3);
+        assert_eq!(
+            interface.events.lock().unwrap()[0],
+            Event::Close(3.into(), CloseReason::RequesedByClient)
+        );
+    }
+
+    #[test]
+    fn notify_resident_and_transient_hints() {
+        let mut interface = NotifyServerInterfaceDBus::default();
+        let hints = HashMap::from([
+            ("resident".to_owned(), Value::from(true)),
+            ("transient".to_owned(), Value::from(true)),
+        ]);
+        interface.notify_from(
+            None,
+            String::new(),
+            0,
+            String::new(),
+            String::new(),
+            String::new(),
+            vec![],
+            hints,
+            0,
+        );
+
+        let events = interface.events.lock().unwrap();
+        let Event::Notify(notification) = &events[0] else {
+            panic!("Expected notify event")
+        };
+        assert!(notification.hints.resident);
+        assert!(notification.hints.transient);
+    }
+
+    #[test]
+    fn capabilities() {
+        let interface = NotifyServerInterfaceDBus::default();
+        assert_eq!(
+            interface.get_capabilities(),
+            ["actions", "body", "body-hyperlinks", "body-markup"]
+        );
+    }
+
+    #[test]
+    fn server_information() {
+        let interface = NotifyServerInterfaceDBus::default();
+        let (_, _, version, spec_version) = interface.get_server_information();
+        assert_eq!(version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(spec_version, "1.2");
+    }
 }